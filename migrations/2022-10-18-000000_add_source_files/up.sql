@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE jobs ADD COLUMN source_files TEXT
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN stop_on_failure BOOLEAN NOT NULL DEFAULT 0
\ No newline at end of file
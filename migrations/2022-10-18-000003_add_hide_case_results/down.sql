@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE contests DROP COLUMN hide_case_results
\ No newline at end of file
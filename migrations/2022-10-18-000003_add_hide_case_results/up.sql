@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN hide_case_results BOOLEAN NOT NULL DEFAULT 0
\ No newline at end of file
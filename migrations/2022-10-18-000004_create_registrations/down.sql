@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE registrations;
+
+ALTER TABLE contests DROP COLUMN registration
\ No newline at end of file
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN registration TEXT;
+
+CREATE TABLE registrations (
+    contest_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    registration_state INTEGER NOT NULL,
+    created_time DATETIME NOT NULL,
+    PRIMARY KEY(contest_id, user_id),
+    FOREIGN KEY(contest_id) REFERENCES contests(id),
+    FOREIGN KEY(user_id) REFERENCES users(id)
+)
\ No newline at end of file
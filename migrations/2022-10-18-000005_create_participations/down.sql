@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE participations;
+
+ALTER TABLE jobs DROP COLUMN submission_kind
\ No newline at end of file
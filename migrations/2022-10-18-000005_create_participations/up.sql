@@ -0,0 +1,11 @@
+-- Your SQL goes here
+ALTER TABLE jobs ADD COLUMN submission_kind INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE participations (
+    contest_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    start_time DATETIME NOT NULL,
+    PRIMARY KEY(contest_id, user_id),
+    FOREIGN KEY(contest_id) REFERENCES contests(id),
+    FOREIGN KEY(user_id) REFERENCES users(id)
+)
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE participations DROP COLUMN participation_kind;
+
+ALTER TABLE contests DROP COLUMN duration
\ No newline at end of file
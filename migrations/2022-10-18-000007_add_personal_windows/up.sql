@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN duration INTEGER;
+
+ALTER TABLE participations ADD COLUMN participation_kind INTEGER NOT NULL DEFAULT 1
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE teams;
+
+ALTER TABLE jobs DROP COLUMN team_id
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE jobs ADD COLUMN team_id INTEGER;
+
+CREATE TABLE teams (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contest_id INTEGER NOT NULL,
+    team_name TEXT NOT NULL,
+    member_ids TEXT NOT NULL,
+    FOREIGN KEY(contest_id) REFERENCES contests(id)
+)
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE announcements;
+
+DROP TABLE clarifications
\ No newline at end of file
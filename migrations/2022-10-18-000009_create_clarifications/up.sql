@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE clarifications (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contest_id INTEGER NOT NULL,
+    problem_id INTEGER,
+    user_id INTEGER NOT NULL,
+    question TEXT NOT NULL,
+    answer TEXT,
+    public BOOLEAN NOT NULL DEFAULT 0,
+    created_time DATETIME NOT NULL,
+    updated_time DATETIME NOT NULL,
+    FOREIGN KEY(contest_id) REFERENCES contests(id),
+    FOREIGN KEY(user_id) REFERENCES users(id)
+);
+
+CREATE TABLE announcements (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contest_id INTEGER NOT NULL,
+    problem_id INTEGER,
+    content TEXT NOT NULL,
+    created_time DATETIME NOT NULL,
+    FOREIGN KEY(contest_id) REFERENCES contests(id)
+)
\ No newline at end of file
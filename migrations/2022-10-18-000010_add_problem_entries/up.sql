@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN problem_entries TEXT NOT NULL DEFAULT '[]'
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE hacks;
+
+ALTER TABLE contests DROP COLUMN challenge_phase
\ No newline at end of file
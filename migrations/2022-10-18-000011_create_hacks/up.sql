@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN challenge_phase TEXT;
+
+CREATE TABLE hacks (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contest_id INTEGER NOT NULL,
+    job_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    input TEXT NOT NULL,
+    hack_state INTEGER NOT NULL,
+    info TEXT NOT NULL,
+    created_time DATETIME NOT NULL,
+    updated_time DATETIME NOT NULL,
+    FOREIGN KEY(contest_id) REFERENCES contests(id),
+    FOREIGN KEY(job_id) REFERENCES jobs(id),
+    FOREIGN KEY(user_id) REFERENCES users(id)
+)
\ No newline at end of file
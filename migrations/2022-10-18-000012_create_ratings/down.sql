@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE ratings;
+
+ALTER TABLE contests DROP COLUMN rated
\ No newline at end of file
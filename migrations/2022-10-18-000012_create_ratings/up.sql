@@ -0,0 +1,13 @@
+-- Your SQL goes here
+ALTER TABLE contests ADD COLUMN rated BOOLEAN NOT NULL DEFAULT 0;
+
+CREATE TABLE ratings (
+    contest_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    user_rank INTEGER NOT NULL,
+    old_rating INTEGER NOT NULL,
+    new_rating INTEGER NOT NULL,
+    PRIMARY KEY(contest_id, user_id),
+    FOREIGN KEY(contest_id) REFERENCES contests(id),
+    FOREIGN KEY(user_id) REFERENCES users(id)
+)
\ No newline at end of file
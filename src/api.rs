@@ -1,8 +1,17 @@
 use chrono::{SecondsFormat, Utc};
 use serde::Serializer;
 
+pub mod announcements;
+pub mod clarifications;
 pub mod contests;
+pub mod hacks;
 pub mod jobs;
+pub mod participations;
+pub mod problems;
+pub mod ratings;
+pub mod registrations;
+pub mod standings;
+pub mod teams;
 pub mod users;
 
 pub mod err;
@@ -0,0 +1,129 @@
+use actix_web::{
+    get, post,
+    web::{self, Data, Json, Path, Query},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{persistent::models, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    clarifications::PollQuery,
+    contests::load_contest,
+    err::{Error, Reason},
+};
+
+/// A notice from the judges to everyone in a contest
+#[derive(Serialize)]
+pub struct Announcement {
+    pub id: u32,
+    pub contest_id: u32,
+    pub problem_id: Option<u32>,
+    pub content: String,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub created_time: DateTime<Utc>,
+}
+
+impl From<models::Announcement> for Announcement {
+    fn from(announcement: models::Announcement) -> Self {
+        Self {
+            id: announcement.id as u32,
+            contest_id: announcement.contest_id as u32,
+            problem_id: announcement.problem_id.map(|pid| pid as u32),
+            content: announcement.content,
+            created_time: announcement.created_time.and_local_timezone(Utc).unwrap(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AnnouncementForm {
+    #[serde(default)]
+    pub problem_id: Option<u32>,
+    pub content: String,
+}
+
+#[post("/contests/{id}/announcements")]
+pub async fn announce(
+    id: Path<u32>,
+    form: Json<AnnouncementForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Announcement>, Error> {
+    const TARGET: &str = "POST /contests/{id}/announcements";
+    log::info!(target: TARGET, "Request received");
+
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You have no permission to access this service".to_string(),
+        ));
+    }
+
+    let cid = id.into_inner();
+    let AnnouncementForm {
+        problem_id,
+        content,
+    } = form.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let contest = load_contest(conn, TARGET, cid)?;
+    if let Some(pid) = problem_id.filter(|pid| !contest.problem_ids.contains(pid)) {
+        log::info!(target: TARGET, "Problem {pid} not in contest {cid}");
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            format!("Problem {pid} not in contest {cid}"),
+        ));
+    }
+    if content.trim().is_empty() {
+        log::info!(target: TARGET, "Empty announcement");
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            "Announcement can't be empty".to_string(),
+        ));
+    }
+
+    let announcement = conn.immediate_transaction(|conn| {
+        let id = models::announcements_count(conn)? + 1;
+        models::new_announcement(
+            conn,
+            models::Announcement {
+                id,
+                contest_id: cid as i32,
+                problem_id: problem_id.map(|pid| pid as i32),
+                content,
+                created_time: Utc::now().naive_utc(),
+            },
+        )
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(announcement.into()))
+}
+
+#[get("/contests/{id}/announcements")]
+pub async fn get_announcements(
+    id: Path<u32>,
+    query: Query<PollQuery>,
+    pool: Data<DbPool>,
+) -> Result<Json<Vec<Announcement>>, Error> {
+    const TARGET: &str = "GET /contests/{id}/announcements";
+    log::info!(target: TARGET, "Request received");
+
+    let cid = id.into_inner();
+    let since = query.since.map(|since| since.naive_utc());
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    load_contest(conn, TARGET, cid)?;
+    let announcements = models::get_announcements(conn, cid as i32, since)?
+        .into_iter()
+        .map(|announcement| announcement.into())
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(announcements))
+}
@@ -0,0 +1,220 @@
+use actix_web::{
+    get, post, put,
+    web::{self, Data, Json, Path, Query},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{persistent::models, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::load_contest,
+    err::{Error, Reason},
+};
+
+/// A question from a user to the judges of a contest
+#[derive(Serialize)]
+pub struct Clarification {
+    pub id: u32,
+    pub contest_id: u32,
+    pub problem_id: Option<u32>,
+    pub user_id: u32,
+    pub question: String,
+    pub answer: Option<String>,
+    /// Whether the clarification is broadcast to everyone in the contest
+    pub public: bool,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub created_time: DateTime<Utc>,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub updated_time: DateTime<Utc>,
+}
+
+impl From<models::Clarification> for Clarification {
+    fn from(clarification: models::Clarification) -> Self {
+        Self {
+            id: clarification.id as u32,
+            contest_id: clarification.contest_id as u32,
+            problem_id: clarification.problem_id.map(|pid| pid as u32),
+            user_id: clarification.user_id as u32,
+            question: clarification.question,
+            answer: clarification.answer,
+            public: clarification.public,
+            created_time: clarification.created_time.and_local_timezone(Utc).unwrap(),
+            updated_time: clarification.updated_time.and_local_timezone(Utc).unwrap(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AskForm {
+    pub user_id: u32,
+    #[serde(default)]
+    pub problem_id: Option<u32>,
+    pub question: String,
+}
+
+#[derive(Deserialize)]
+pub struct AnswerForm {
+    pub answer: String,
+    #[serde(default)]
+    pub public: bool,
+}
+
+/// Only fetch what changed since the last poll
+#[derive(Deserialize)]
+pub struct PollQuery {
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[post("/contests/{id}/clarifications")]
+pub async fn ask(
+    id: Path<u32>,
+    form: Json<AskForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Clarification>, Error> {
+    const TARGET: &str = "POST /contests/{id}/clarifications";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only ask for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to ask on behalf of others".to_string(),
+        ));
+    }
+
+    let cid = id.into_inner();
+    let AskForm {
+        user_id: uid,
+        problem_id,
+        question,
+    } = form.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let clarification = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        if !contest.user_ids.contains(&uid) {
+            log::info!(target: TARGET, "User {uid} not in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("User {uid} not in contest {cid}"),
+            ));
+        }
+        if let Some(pid) = problem_id.filter(|pid| !contest.problem_ids.contains(pid)) {
+            log::info!(target: TARGET, "Problem {pid} not in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Problem {pid} not in contest {cid}"),
+            ));
+        }
+        if question.trim().is_empty() {
+            log::info!(target: TARGET, "Empty question");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                "Question can't be empty".to_string(),
+            ));
+        }
+        let now = Utc::now();
+        if now < contest.from || now > contest.to {
+            log::info!(target: TARGET, "Contest {cid} is not running");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} is not running"),
+            ));
+        }
+
+        let id = models::clarifications_count(conn)? + 1;
+        models::new_clarification(
+            conn,
+            models::Clarification {
+                id,
+                contest_id: cid as i32,
+                problem_id: problem_id.map(|pid| pid as i32),
+                user_id: uid as i32,
+                question,
+                answer: None,
+                public: false,
+                created_time: now.naive_utc(),
+                updated_time: now.naive_utc(),
+            },
+        )
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(clarification.into()))
+}
+
+#[get("/contests/{id}/clarifications")]
+pub async fn get_clarifications(
+    id: Path<u32>,
+    query: Query<PollQuery>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<Clarification>>, Error> {
+    const TARGET: &str = "GET /contests/{id}/clarifications";
+    log::info!(target: TARGET, "Request received");
+
+    let cid = id.into_inner();
+    let since = query.since.map(|since| since.naive_utc());
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    load_contest(conn, TARGET, cid)?;
+    #[allow(unused_mut)]
+    let mut clarifications = models::get_clarifications(conn, cid as i32, since)?;
+    // Users only see their own clarifications and the broadcast ones
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        clarifications.retain(|clarification| {
+            clarification.public || clarification.user_id == user_claims.id as i32
+        });
+    }
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(
+        clarifications
+            .into_iter()
+            .map(|clarification| clarification.into())
+            .collect(),
+    ))
+}
+
+#[put("/contests/{id}/clarifications/{clarification_id}")]
+pub async fn answer(
+    path: Path<(u32, u32)>,
+    form: Json<AnswerForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Clarification>, Error> {
+    const TARGET: &str = "PUT /contests/{id}/clarifications/{clarification_id}";
+    log::info!(target: TARGET, "Request received");
+
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You have no permission to access this service".to_string(),
+        ));
+    }
+
+    let (cid, clid) = path.into_inner();
+    let AnswerForm { answer, public } = form.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let clarification = conn.immediate_transaction(|conn| {
+        load_contest(conn, TARGET, cid)?;
+        let mut clarification = models::get_clarification(conn, cid as i32, clid as i32)?;
+
+        clarification.answer = Some(answer);
+        clarification.public = public;
+        clarification.updated_time = Utc::now().naive_utc();
+        models::update_clarification(conn, clarification)
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(clarification.into()))
+}
@@ -7,10 +7,28 @@ use actix_web::{
     get, post,
     web::{self, Data, Json, Path, Query},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{
+    backend::{self, Backend},
+    deserialize::FromSql,
+    serialize::{IsNull, Output, ToSql},
+    sql_types::Text,
+    sqlite::Sqlite,
+    AsExpression, FromSqlRow, SqliteConnection,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{api::err::Reason, config::Problem, persistent::models::User};
+use crate::{
+    api::{
+        err::Reason,
+        hacks::HackState,
+        jobs::{JobResult, JobStatus, SubmissionKind},
+        participations::Participation,
+        teams::{self, Team},
+    },
+    config::Problem,
+    persistent::models::User,
+};
 use crate::{config::Config, persistent::models, DbPool};
 
 #[cfg(feature = "authorization")]
@@ -18,6 +36,20 @@ use crate::authorization::{Role, UserClaims};
 
 use super::err::Error;
 
+/// Minutes after which a problem would be worth nothing under the codeforces rule
+const DECAY_MINUTES: f64 = 250.0;
+
+/// Fraction of the full score of a problem lost for each rejected attempt
+const ATTEMPT_PENALTY: f64 = 0.1;
+
+/// Fraction of the full score of a problem kept however late or often it is solved
+const MIN_FRACTION: f64 = 0.3;
+
+/// Points earned for a successful hack, and lost for an unsuccessful one, as fractions of the
+/// largest full score among the problems of the contest, like 100 and 50 against 1000 on Codeforces
+const HACK_REWARD: f64 = 0.1;
+const HACK_PENALTY: f64 = 0.05;
+
 #[derive(Serialize, Deserialize)]
 pub struct Contest {
     pub id: Option<u32>,
@@ -29,6 +61,141 @@ pub struct Contest {
     pub problem_ids: Vec<u32>,
     pub user_ids: Vec<u32>,
     pub submission_limit: u32,
+    /// Skip the remaining cases of a submission after its first failure
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    /// Hide the results of cases from users until the contest ends
+    #[serde(default)]
+    pub hide_case_results: bool,
+    /// How users register for the contest by themselves, `None` if they can't
+    #[serde(default)]
+    pub registration: Option<RegistrationRule>,
+    /// Accept submissions out of the competition after the contest ends
+    #[serde(default)]
+    pub allow_upsolving: bool,
+    /// Seconds each participant has from their own start, `None` if all share the contest time
+    #[serde(default)]
+    pub duration: Option<u32>,
+    /// Settings of problems specific to the contest
+    #[serde(default)]
+    pub problems: ContestProblems,
+    /// When contestants may hack the accepted submissions of others, `None` if they can't
+    #[serde(default)]
+    pub challenge_phase: Option<ChallengePhase>,
+    /// Whether the ratings of participants change with the results once it is finalized
+    #[serde(default)]
+    pub rated: bool,
+}
+
+/// Settings of a problem in a contest, in place of those shared by all contests
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContestProblem {
+    pub id: u32,
+    /// Shown in place of the id, e.g. `A`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Scale the score earned on the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplier: Option<f64>,
+    /// Full score of the problem, in place of the sum of the scores of its cases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submission_limit: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+#[serde(transparent)]
+pub struct ContestProblems(pub Vec<ContestProblem>);
+
+impl ToSql<Text, Sqlite> for ContestProblems
+where
+    String: ToSql<Text, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(serde_json::to_string(self)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for ContestProblems
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        let s = String::from_sql(bytes)?;
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+
+/// When and how users may register for a contest by themselves
+#[derive(Clone, Debug, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct RegistrationRule {
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub from: DateTime<Utc>,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub to: DateTime<Utc>,
+    /// Invite code required to register, only shown to authors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Whether registrations wait for the approval of an author
+    #[serde(default)]
+    pub approval: bool,
+}
+
+impl ToSql<Text, Sqlite> for RegistrationRule
+where
+    String: ToSql<Text, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(serde_json::to_string(self)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for RegistrationRule
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        let s = String::from_sql(bytes)?;
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+
+/// The period in which contestants may hack the accepted submissions of others
+#[derive(Clone, Debug, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct ChallengePhase {
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub from: DateTime<Utc>,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub to: DateTime<Utc>,
+}
+
+impl ToSql<Text, Sqlite> for ChallengePhase
+where
+    String: ToSql<Text, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(serde_json::to_string(self)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for ChallengePhase
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        let s = String::from_sql(bytes)?;
+        Ok(serde_json::from_str(&s)?)
+    }
 }
 
 impl From<models::Contest> for Contest {
@@ -38,21 +205,94 @@ impl From<models::Contest> for Contest {
             name: contest.contest_name,
             from: contest.contest_from.and_local_timezone(Utc).unwrap(),
             to: contest.contest_to.and_local_timezone(Utc).unwrap(),
-            problem_ids: contest
-                .problem_ids
-                .split(',')
-                .map(|s| s.parse::<u32>().unwrap())
-                .collect(),
-            user_ids: contest
-                .user_ids
-                .split(',')
-                .map(|s| s.parse::<u32>().unwrap())
-                .collect(),
+            problem_ids: parse_ids(&contest.problem_ids),
+            user_ids: parse_ids(&contest.user_ids),
             submission_limit: contest.submission_limit as u32,
+            stop_on_failure: contest.stop_on_failure,
+            hide_case_results: contest.hide_case_results,
+            registration: contest.registration,
+            allow_upsolving: contest.allow_upsolving,
+            duration: contest.duration.map(|duration| duration as u32),
+            problems: contest.problem_entries,
+            challenge_phase: contest.challenge_phase,
+            rated: contest.rated,
+        }
+    }
+}
+
+impl Contest {
+    /// How long a participant may compete from their own start
+    pub fn length(&self) -> Duration {
+        self.duration
+            .map(|duration| Duration::seconds(duration as i64))
+            .unwrap_or(self.to - self.from)
+    }
+
+    /// Get the settings of a problem in the contest, if any
+    pub fn problem(&self, pid: u32) -> Option<&ContestProblem> {
+        self.problems.0.iter().find(|entry| entry.id == pid)
+    }
+
+    /// The label of a problem in the contest, which is its id unless one is given
+    pub fn label_of(&self, pid: u32) -> String {
+        self.problem(pid)
+            .and_then(|entry| entry.label.clone())
+            .unwrap_or_else(|| pid.to_string())
+    }
+
+    /// How many times a user may submit to a problem
+    pub fn submission_limit_of(&self, pid: u32) -> u32 {
+        self.problem(pid)
+            .and_then(|entry| entry.submission_limit)
+            .unwrap_or(self.submission_limit)
+    }
+
+    /// The factor to scale the score earned on a problem by
+    pub fn scale_of(&self, problem: &Problem) -> f64 {
+        match self.problem(problem.id) {
+            Some(ContestProblem {
+                score: Some(score), ..
+            }) if problem.full_score() > 0.0 => score / problem.full_score(),
+            Some(ContestProblem {
+                multiplier: Some(multiplier),
+                ..
+            }) => *multiplier,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Contest {
+    /// Hide the invite code from those who are not allowed to see it, which is everyone
+    /// without authorization
+    fn hide_code(&mut self) {
+        if let Some(rule) = &mut self.registration {
+            rule.code = None;
         }
     }
 }
 
+/// Parse a comma-separated list of ids, which is empty for an empty string
+pub fn parse_ids(ids: &str) -> Vec<u32> {
+    ids.split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u32>().unwrap())
+        .collect()
+}
+
+/// Get a contest, with a detailed description when not found
+pub fn load_contest(conn: &mut SqliteConnection, target: &str, cid: u32) -> Result<Contest, Error> {
+    Ok(models::get_contest(conn, cid as i32)
+        .map_err(|err| match err.reason {
+            Reason::NotFound => {
+                log::info!(target: target, "No such contest: {cid}");
+                Error::new(Reason::NotFound, format!("Contest {cid} not found."))
+            }
+            _ => err,
+        })?
+        .into())
+}
+
 #[post("/contests")]
 pub async fn update_contest(
     contest: Json<Contest>,
@@ -87,6 +327,57 @@ pub async fn update_contest(
         }
     }
 
+    // Check validity of problem settings
+    let mut labels = HashSet::new();
+    for (index, entry) in contest.problems.0.iter().enumerate() {
+        let pid = entry.id;
+        let invalid = if !contest.problem_ids.contains(&pid) {
+            Some(format!("Problem {pid} not in the contest"))
+        } else if contest.problems.0[..index].iter().any(|e| e.id == pid) {
+            Some(format!("Duplicate settings of problem {pid}"))
+        } else if entry.multiplier.is_some() && entry.score.is_some() {
+            Some(format!("Both multiplier and score given for problem {pid}"))
+        } else if entry.multiplier.or(entry.score).is_some_and(|x| x < 0.0) {
+            Some(format!("Negative score of problem {pid}"))
+        } else if entry
+            .label
+            .as_ref()
+            .is_some_and(|label| !labels.insert(label))
+        {
+            Some(format!("Duplicate label of problem {pid}"))
+        } else {
+            None
+        };
+        if let Some(message) = invalid {
+            log::info!(target: TARGET, "{message}");
+            return Err(Error::new(Reason::InvalidArgument, message));
+        }
+    }
+
+    // Hacks can only be made in a period of time
+    if let Some(phase) = contest
+        .challenge_phase
+        .as_ref()
+        .filter(|phase| phase.from > phase.to)
+    {
+        log::info!(target: TARGET, "Invalid challenge phase: {phase:?}");
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            "Challenge phase ends before it starts".to_string(),
+        ));
+    }
+
+    // Members of a team would start their personal windows at different times
+    if let (Some(id), Some(_)) = (contest.id, contest.duration) {
+        if !teams::get_teams(conn, id)?.is_empty() {
+            log::info!(target: TARGET, "Contest {id} has teams");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Contest {id} has teams, so it can't have personal windows"),
+            ));
+        }
+    }
+
     // Check validity of users
     let user_count = models::user_count(conn)? as u32;
     for uid in &contest.user_ids {
@@ -123,38 +414,50 @@ pub async fn update_contest(
 }
 
 #[get("/contests")]
-pub async fn get_contests(pool: Data<DbPool>) -> Result<Json<Vec<Contest>>, Error> {
+pub async fn get_contests(
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<Contest>>, Error> {
     const TARGET: &str = "GET /contests";
     log::info!(target: TARGET, "Request received");
 
     let conn = &mut web::block(move || pool.get()).await??;
 
-    let contests: Vec<Contest> = models::get_contests(conn)?
+    #[allow(unused_mut)]
+    let mut contests: Vec<Contest> = models::get_contests(conn)?
         .into_iter()
         .map(|c| c.into())
         .collect();
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        contests.iter_mut().for_each(Contest::hide_code);
+    }
+    #[cfg(not(feature = "authorization"))]
+    contests.iter_mut().for_each(Contest::hide_code);
     log::info!(target: TARGET, "Request done");
     Ok(Json(contests))
 }
 
 #[get("/contests/{id}")]
-pub async fn get_contest(id: Path<u32>, pool: Data<DbPool>) -> Result<Json<Contest>, Error> {
+pub async fn get_contest(
+    id: Path<u32>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Contest>, Error> {
     const TARGET: &str = "GET /contests/{id}";
     log::info!(target: TARGET, "Request received");
 
-    let id = id.into_inner() as i32;
+    let id = id.into_inner();
 
     let conn = &mut web::block(move || pool.get()).await??;
 
-    let contest: Contest = models::get_contest(conn, id)
-        .map_err(|err| match err.reason {
-            Reason::NotFound => {
-                log::info!(target: TARGET, "No such contest: {id}");
-                Error::new(Reason::NotFound, format!("Contest {id} not found."))
-            }
-            _ => err,
-        })?
-        .into();
+    let mut contest = load_contest(conn, TARGET, id)?;
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        contest.hide_code();
+    }
+    #[cfg(not(feature = "authorization"))]
+    contest.hide_code();
     log::info!(target: TARGET, "Request done");
     Ok(Json(contest))
 }
@@ -164,6 +467,15 @@ pub async fn get_contest(id: Path<u32>, pool: Data<DbPool>) -> Result<Json<Conte
 pub enum ScoringRule {
     Latest,
     Highest,
+    /// The full score of the first accepted submission, decayed over time and attempts
+    Codeforces,
+}
+
+/// Score a problem solved some minutes into the contest after some rejected attempts
+fn decayed_score(full_score: f64, minutes: f64, rejected: usize) -> f64 {
+    let score = full_score * (1.0 - minutes / DECAY_MINUTES)
+        - full_score * ATTEMPT_PENALTY * rejected as f64;
+    score.max(full_score * MIN_FRACTION)
 }
 
 #[derive(Deserialize)]
@@ -176,14 +488,14 @@ pub enum TieBreaker {
 }
 
 impl TieBreaker {
-    /// Compare the ranking of two users
+    /// Compare the ranking of two users, given their results and points earned by hacking
     pub fn compare(
         &self,
-        (id_a, a): &(u32, &HashMap<u32, ProblemResult>),
-        (id_b, b): &(u32, &HashMap<u32, ProblemResult>),
+        (id_a, a, bonus_a): &(u32, &HashMap<u32, ProblemResult>, f64),
+        (id_b, b, bonus_b): &(u32, &HashMap<u32, ProblemResult>, f64),
     ) -> Ordering {
-        let total_score_a: f64 = a.values().map(|result| result.score).sum();
-        let total_score_b: f64 = b.values().map(|result| result.score).sum();
+        let total_score_a: f64 = a.values().map(|result| result.score).sum::<f64>() + bonus_a;
+        let total_score_b: f64 = b.values().map(|result| result.score).sum::<f64>() + bonus_b;
         match total_score_a.total_cmp(&total_score_b).reverse() {
             Ordering::Equal => (),
             ord => return ord,
@@ -222,13 +534,50 @@ impl TieBreaker {
 pub struct RankingRule {
     pub scoring_rule: Option<ScoringRule>,
     pub tie_breaker: Option<TieBreaker>,
+    /// Merge virtual participants into the standings
+    #[serde(default)]
+    pub include_virtual: bool,
+    /// Count submissions made out of the competition after the contest
+    #[serde(default)]
+    pub include_upsolving: bool,
+    /// Rank as of this many seconds after each participant started
+    pub elapsed: Option<u32>,
 }
 
 #[derive(Serialize)]
 pub struct RankingItem {
+    /// The user ranked, unless a team is ranked instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<RankedTeam>,
+    pub kind: SubmissionKind,
+    pub rank: u32,
+    pub scores: Vec<f64>,
+    /// Seconds used in a personal window, for contests with one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_used: Option<u32>,
+    /// Points earned by hacking, under the codeforces rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hack_score: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct RankedTeam {
+    pub id: u32,
+    pub name: String,
+    pub members: Vec<User>,
+}
+
+// A participant of a contest, ranked by their submissions of a kind in a window starting at a time
+struct Participant {
+    /// The user, or the member with the smallest id of the team
     user: User,
-    rank: u32,
-    scores: Vec<f64>,
+    team: Option<Team>,
+    kind: SubmissionKind,
+    start: Option<DateTime<Utc>>,
+    /// Seconds used if the window is personal
+    time_used: Option<u32>,
 }
 
 // The result of a problem for a user, for ranking
@@ -261,42 +610,208 @@ pub async fn get_rank_list(
         ));
     }
 
+    let response = rank_list(conn, &config, id, rule.into_inner())?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(response))
+}
+
+/// Rank the participants of an existing contest, or all users for contest 0
+pub fn rank_list(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    id: u32,
+    rule: RankingRule,
+) -> Result<Vec<RankingItem>, Error> {
     let RankingRule {
         scoring_rule,
         tie_breaker,
-    } = rule.into_inner();
+        include_virtual,
+        include_upsolving,
+        elapsed,
+    } = rule;
 
     let scoring_rule = scoring_rule.unwrap_or(ScoringRule::Latest);
     let tie_breaker = tie_breaker.unwrap_or(TieBreaker::Default);
 
-    let users: Vec<User>;
+    let mut participants: Vec<Participant> = vec![];
     let problems: Vec<&Problem>;
+    // Scores on each problem are scaled by the settings of the contest
+    let mut scales: Vec<f64> = vec![];
+    let mut contest_from = None;
 
     if id == 0 {
-        users = models::get_users(conn)?;
+        for user in models::get_users(conn)? {
+            participants.push(Participant {
+                user,
+                team: None,
+                kind: SubmissionKind::Official,
+                start: None,
+                time_used: None,
+            });
+        }
         problems = config.problems.iter().collect();
     } else {
         let contest: Contest = models::get_contest(conn, id as i32)?.into();
-        users =
-            models::get_some_users(conn, contest.user_ids.iter().map(|id| *id as i32).collect())?;
+        let now = Utc::now();
+        let mut windows = HashMap::new();
+        let mut virtual_windows = vec![];
+        for participation in models::get_participations(conn, id as i32)? {
+            let window = Participation::new(&participation, &contest);
+            match window.kind {
+                SubmissionKind::Official => {
+                    windows.insert(window.user_id, window);
+                }
+                _ => virtual_windows.push(window),
+            }
+        }
+        let time_used =
+            |window: &Participation| (now.min(window.to) - window.from).num_seconds() as u32;
+
+        // Members of a team are ranked as the team
+        let teams = teams::get_teams(conn, id)?;
+        let members: HashSet<u32> = teams
+            .iter()
+            .flat_map(|team| team.member_ids.iter().copied())
+            .collect();
+        let users = models::get_some_users(
+            conn,
+            contest
+                .user_ids
+                .iter()
+                .filter(|id| !members.contains(id))
+                .map(|id| *id as i32)
+                .collect(),
+        )?;
+        for user in users {
+            // Those who haven't started a personal window have used no time yet
+            let (start, used) = match (contest.duration, windows.get(&(user.id as u32))) {
+                (Some(_), Some(window)) => (window.from, Some(time_used(window))),
+                (Some(_), None) => (contest.from, Some(0)),
+                (None, _) => (contest.from, None),
+            };
+            participants.push(Participant {
+                user,
+                team: None,
+                kind: SubmissionKind::Official,
+                start: Some(start),
+                time_used: used,
+            });
+        }
+        // Contests with personal windows have no teams, so teams start with the contest
+        for team in teams {
+            let uid = *team.member_ids.iter().min().unwrap();
+            participants.push(Participant {
+                user: models::get_user(conn, uid as i32)?,
+                team: Some(team),
+                kind: SubmissionKind::Official,
+                start: Some(contest.from),
+                time_used: None,
+            });
+        }
+        if include_virtual {
+            for window in virtual_windows {
+                let user = models::get_user(conn, window.user_id as i32)?;
+                participants.push(Participant {
+                    user,
+                    team: None,
+                    kind: SubmissionKind::Virtual,
+                    start: Some(window.from),
+                    time_used: Some(time_used(&window)),
+                });
+            }
+        }
         problems = contest
             .problem_ids
             .iter()
             .filter_map(|id| config.get_problem(*id))
             .collect();
+        scales = problems
+            .iter()
+            .map(|problem| contest.scale_of(problem))
+            .collect();
+        contest_from = Some(contest.from);
     }
 
-    let mut rank_list: Vec<(u32, HashMap<u32, ProblemResult>)> = vec![];
-    for user in &users {
+    // Hacks are worth the same on every problem, so they are scaled by the largest full score
+    let hack_unit = problems
+        .iter()
+        .zip(&scales)
+        .map(|(problem, scale)| problem.full_score() * scale)
+        .fold(0.0, f64::max);
+
+    let mut rank_list: Vec<(u32, HashMap<u32, ProblemResult>, Option<f64>)> = vec![];
+    for participant in &participants {
+        let Participant {
+            user,
+            team,
+            kind,
+            start,
+            ..
+        } = participant;
+        let mut kinds = vec![*kind];
+        if include_upsolving {
+            kinds.push(SubmissionKind::Upsolving);
+        }
+        let scope = models::SubmissionScope {
+            kinds,
+            until: start
+                .zip(elapsed)
+                .map(|(start, elapsed)| (start + Duration::seconds(elapsed as i64)).naive_utc()),
+            team: team.as_ref().map(|team| team.id as i32),
+        };
+        // Submissions in personal windows are ranked as if they were made at the same point of
+        // the contest
+        let offset = match (start, contest_from) {
+            (Some(start), Some(from)) => *start - from,
+            _ => Duration::zero(),
+        };
+
         let mut map = HashMap::<u32, ProblemResult>::new();
-        for problem in &problems {
+        for (index, problem) in problems.iter().enumerate() {
+            let scale = scales.get(index).copied().unwrap_or(1.0);
+            // Attempts rejected before the first accepted submission, under the codeforces rule
+            let mut rejected = 0;
             // Fetch the problem result for a user
             let result = match scoring_rule {
-                ScoringRule::Latest => {
-                    models::get_latest_submission(conn, user.id, problem.id as i32, id as i32)
-                }
-                ScoringRule::Highest => {
-                    models::get_highest_submission(conn, user.id, problem.id as i32, id as i32)
+                ScoringRule::Latest => models::get_latest_submission(
+                    conn,
+                    user.id,
+                    problem.id as i32,
+                    id as i32,
+                    &scope,
+                ),
+                ScoringRule::Highest => models::get_highest_submission(
+                    conn,
+                    user.id,
+                    problem.id as i32,
+                    id as i32,
+                    &scope,
+                ),
+                ScoringRule::Codeforces => {
+                    let mut submissions = models::get_submissions(
+                        conn,
+                        user.id,
+                        problem.id as i32,
+                        id as i32,
+                        &scope,
+                    )?;
+                    let accepted = submissions
+                        .iter()
+                        .position(|job| job.result == JobResult::Accepted)
+                        .unwrap_or(submissions.len());
+                    // Neither compilation errors nor system errors count as attempts
+                    rejected = submissions[..accepted]
+                        .iter()
+                        .filter(|job| {
+                            job.job_state == JobStatus::Finished
+                                && job.result != JobResult::CompilationError
+                                && job.result != JobResult::SystemError
+                        })
+                        .count();
+                    // Without an accepted submission, the latest one scores nothing
+                    submissions.truncate(accepted + 1);
+                    Ok(submissions.pop())
                 }
             }?;
             // No submission on this problem
@@ -304,10 +819,20 @@ pub async fn get_rank_list(
                 continue;
             }
             let job = result.unwrap();
-            let score = job.score;
-            let submission_time = job.created_time.and_local_timezone(Utc).unwrap();
+            let submission_time = job.created_time.and_local_timezone(Utc).unwrap() - offset;
+            let score = match scoring_rule {
+                ScoringRule::Codeforces if job.result == JobResult::Accepted => {
+                    let minutes = contest_from.map_or(0.0, |from| {
+                        (submission_time - from).num_seconds() as f64 / 60.0
+                    });
+                    decayed_score(problem.full_score() * scale, minutes, rejected)
+                }
+                ScoringRule::Codeforces => 0.0,
+                _ => job.score * scale,
+            };
             let count =
-                models::get_submission_count(conn, user.id, problem.id as i32, id as i32)? as u32;
+                models::get_submission_count(conn, user.id, problem.id as i32, id as i32, &scope)?
+                    as u32;
             map.insert(
                 problem.id,
                 ProblemResult {
@@ -317,12 +842,39 @@ pub async fn get_rank_list(
                 },
             );
         }
-        rank_list.push((user.id as u32, map));
+
+        // Hacks made by the participant, or any member of their team, count under the
+        // codeforces rule
+        let hack_score = match (&scoring_rule, kind) {
+            (ScoringRule::Codeforces, SubmissionKind::Official) if id != 0 => {
+                let uids: Vec<i32> = match team {
+                    Some(team) => team.member_ids.iter().map(|id| *id as i32).collect(),
+                    None => vec![user.id],
+                };
+                let mut count = |state| {
+                    models::count_hacks(conn, id as i32, &uids, state, scope.until)
+                        .map(|count| count as f64)
+                };
+                Some(
+                    (count(HackState::Successful)? * HACK_REWARD
+                        - count(HackState::Unsuccessful)? * HACK_PENALTY)
+                        * hack_unit,
+                )
+            }
+            _ => None,
+        };
+        rank_list.push((user.id as u32, map, hack_score));
     }
 
-    // Ranking according to the tie breaker rule
-    rank_list.sort_by(|(id_a, a), (id_b, b)| {
-        match tie_breaker.compare(&(*id_a, a), &(*id_b, b)) {
+    // Ranking according to the tie breaker rule, listing participants tied on it by user id
+    let mut order: Vec<usize> = (0..rank_list.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (id_a, a, bonus_a) = &rank_list[a];
+        let (id_b, b, bonus_b) = &rank_list[b];
+        match tie_breaker.compare(
+            &(*id_a, a, bonus_a.unwrap_or_default()),
+            &(*id_b, b, bonus_b.unwrap_or_default()),
+        ) {
             // If equal, sort in ascending order by user id
             // Note that this will not affect the ranking, which is decided by the tie breaker
             Ordering::Equal => id_a.cmp(id_b),
@@ -332,19 +884,37 @@ pub async fn get_rank_list(
 
     // Construct the response
     let mut response: Vec<RankingItem> = vec![];
-    for (rank, (user_id, _)) in rank_list.iter().enumerate() {
+    for (rank, &index) in order.iter().enumerate() {
+        let (user_id, results, hack_score) = &rank_list[index];
         let last_rank = response.last().map(|item| item.rank).unwrap_or_default();
+        let (user, team) = match &participants[index].team {
+            Some(team) => (
+                None,
+                Some(RankedTeam {
+                    id: team.id,
+                    name: team.name.clone(),
+                    members: models::get_some_users(
+                        conn,
+                        team.member_ids.iter().map(|id| *id as i32).collect(),
+                    )?,
+                }),
+            ),
+            None => (Some(models::get_user(conn, *user_id as i32)?), None),
+        };
         response.push(RankingItem {
-            user: models::get_user(conn, *user_id as i32)?,
+            user,
+            team,
+            kind: participants[index].kind,
             // Calculate rank
             rank: if rank == 0 {
                 1
             } else {
                 // If the two users are ranked equal by the tie breaker rule,
                 // assign them the same ranking
+                let (last_id, last_results, last_hack_score) = &rank_list[order[rank - 1]];
                 if tie_breaker.compare(
-                    &(rank_list[rank].0, &rank_list[rank].1),
-                    &(rank_list[rank - 1].0, &rank_list[rank - 1].1),
+                    &(*user_id, results, hack_score.unwrap_or_default()),
+                    &(*last_id, last_results, last_hack_score.unwrap_or_default()),
                 ) == Ordering::Equal
                 {
                     last_rank
@@ -352,32 +922,15 @@ pub async fn get_rank_list(
                     rank as u32 + 1
                 }
             },
+            // If no submissions on a problem are found, set the score to 0
             scores: problems
                 .iter()
-                .map(|p| {
-                    // If no submissions on a problem are found, set the score to 0
-                    match scoring_rule {
-                        ScoringRule::Latest => models::get_latest_submission(
-                            conn,
-                            *user_id as i32,
-                            p.id as i32,
-                            id as i32,
-                        ),
-                        ScoringRule::Highest => models::get_highest_submission(
-                            conn,
-                            *user_id as i32,
-                            p.id as i32,
-                            id as i32,
-                        ),
-                    }
-                    .unwrap()
-                    .map(|s| s.score)
-                    .unwrap_or_default()
-                })
+                .map(|p| results.get(&p.id).map(|r| r.score).unwrap_or_default())
                 .collect(),
+            time_used: participants[index].time_used,
+            hack_score: *hack_score,
         })
     }
 
-    log::info!(target: TARGET, "Request done");
-    Ok(Json(response))
+    Ok(response)
 }
@@ -0,0 +1,288 @@
+use actix_web::{
+    get, post,
+    web::{self, Data, Json, Path, Query},
+};
+use amiquip::Channel;
+use chrono::{DateTime, Utc};
+use diesel::{
+    backend::{self, Backend},
+    deserialize::FromSql,
+    serialize::{IsNull, Output, ToSql},
+    sql_types::Integer,
+    sqlite::Sqlite,
+    AsExpression, FromSqlRow,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::judge::Message;
+use crate::persistent::models::{self, HackFilter};
+use crate::{config::Config, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::load_contest,
+    err::{Error, Reason},
+    jobs::{self, JobResult, JobStatus, SubmissionKind, MAX_SUBMISSION_SIZE},
+    teams,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Integer)]
+pub enum HackState {
+    Queueing,
+    Running,
+    /// The target failed on the input, which is now counted against it
+    Successful,
+    /// The target passed on the input
+    Unsuccessful,
+    /// The input was rejected by the validator, or the target can no longer be hacked
+    #[serde(rename = "Invalid Input")]
+    Invalid,
+    #[serde(rename = "System Error")]
+    SystemError,
+}
+
+impl ToSql<Integer, Sqlite> for HackState
+where
+    i32: ToSql<Integer, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(*self as i32);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for HackState
+where
+    DB: Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(HackState::Queueing),
+            1 => Ok(HackState::Running),
+            2 => Ok(HackState::Successful),
+            3 => Ok(HackState::Unsuccessful),
+            4 => Ok(HackState::Invalid),
+            5 => Ok(HackState::SystemError),
+            x => Err(format!("Unrecognized enum variant {x}").into()),
+        }
+    }
+}
+
+/// An input made by a contestant to challenge the accepted submission of another
+#[derive(Serialize)]
+pub struct Hack {
+    pub id: u32,
+    pub contest_id: u32,
+    /// The submission challenged
+    pub job_id: u32,
+    /// The user who made the hack
+    pub user_id: u32,
+    pub input: String,
+    pub state: HackState,
+    pub info: String,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub created_time: DateTime<Utc>,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub updated_time: DateTime<Utc>,
+}
+
+impl From<models::Hack> for Hack {
+    fn from(hack: models::Hack) -> Self {
+        Self {
+            id: hack.id as u32,
+            contest_id: hack.contest_id as u32,
+            job_id: hack.job_id as u32,
+            user_id: hack.user_id as u32,
+            input: hack.input,
+            state: hack.hack_state,
+            info: hack.info,
+            created_time: hack.created_time.and_local_timezone(Utc).unwrap(),
+            updated_time: hack.updated_time.and_local_timezone(Utc).unwrap(),
+        }
+    }
+}
+
+#[cfg(feature = "authorization")]
+impl Hack {
+    /// Hide the input of a hack from those other than its author
+    fn redact(&mut self, user_claims: &UserClaims) {
+        if user_claims.role < Role::Author && self.user_id != user_claims.id {
+            self.input.clear();
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HackForm {
+    pub user_id: u32,
+    pub job_id: u32,
+    pub input: String,
+}
+
+/// Queue a hack to be judged
+fn queue_hack(id: i32, channel: &Channel) -> Result<(), Error> {
+    jobs::publish(Message::Hack(id), channel)
+}
+
+#[post("/hacks")]
+pub async fn new_hack(
+    form: Json<HackForm>,
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    amqp_channel: Data<Channel>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Hack>, Error> {
+    const TARGET: &str = "POST /hacks";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only hack for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to hack on behalf of others".to_string(),
+        ));
+    }
+
+    let HackForm {
+        user_id: uid,
+        job_id: jid,
+        input,
+    } = form.into_inner();
+    if input.len() > MAX_SUBMISSION_SIZE {
+        log::info!(target: TARGET, "Input too large");
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            "Input too large".to_string(),
+        ));
+    }
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let hack = conn.immediate_transaction(|conn| {
+        let job = models::get_job(conn, jid as i32)?;
+        let cid = job.contest_id as u32;
+        if cid == 0 {
+            log::info!(target: TARGET, "Job {jid} not in a contest");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Job {jid} not in a contest"),
+            ));
+        }
+        let contest = load_contest(conn, TARGET, cid)?;
+        let pid = job.problem_id as u32;
+        if !config.get_problem(pid).is_some_and(|p| p.is_hackable()) {
+            log::info!(target: TARGET, "Problem {pid} can't be hacked");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Problem {pid} can't be hacked"),
+            ));
+        }
+        if !contest.user_ids.contains(&uid) {
+            log::info!(target: TARGET, "User {uid} not in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("User {uid} not in contest {cid}"),
+            ));
+        }
+        // Neither you nor your teammates can hack the submissions of your team
+        let team = teams::find_team(conn, cid, uid)?;
+        if job.user_id as u32 == uid
+            || job.team_id.is_some() && job.team_id == team.map(|team| team.id as i32)
+        {
+            log::info!(target: TARGET, "User {uid} hacking their own job {jid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                "You can't hack your own submission".to_string(),
+            ));
+        }
+        if job.submission_kind != SubmissionKind::Official
+            || job.job_state != JobStatus::Finished
+            || job.result != JobResult::Accepted
+        {
+            log::info!(target: TARGET, "Job {jid} is not an accepted submission");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Job {jid} is not an accepted submission"),
+            ));
+        }
+        let now = Utc::now();
+        if !contest
+            .challenge_phase
+            .is_some_and(|phase| phase.from <= now && now <= phase.to)
+        {
+            log::info!(target: TARGET, "Contest {cid} not in its challenge phase");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} is not in its challenge phase"),
+            ));
+        }
+
+        let id = models::hacks_count(conn)? + 1;
+        models::new_hack(
+            conn,
+            models::Hack {
+                id,
+                contest_id: cid as i32,
+                job_id: jid as i32,
+                user_id: uid as i32,
+                input,
+                hack_state: HackState::Queueing,
+                info: String::new(),
+                created_time: now.naive_utc(),
+                updated_time: now.naive_utc(),
+            },
+        )
+    })?;
+    queue_hack(hack.id, &amqp_channel)?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(hack.into()))
+}
+
+#[get("/hacks")]
+pub async fn get_hacks(
+    filter: Query<HackFilter>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<Hack>>, Error> {
+    const TARGET: &str = "GET /hacks";
+    log::info!(target: TARGET, "Request received");
+
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    #[allow(unused_mut)]
+    let mut hacks: Vec<Hack> = models::get_hacks(conn, filter.into_inner())?
+        .into_iter()
+        .map(|hack| hack.into())
+        .collect();
+    #[cfg(feature = "authorization")]
+    hacks.iter_mut().for_each(|hack| hack.redact(&user_claims));
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(hacks))
+}
+
+#[get("/hacks/{id}")]
+pub async fn get_hack(
+    id: Path<u32>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Hack>, Error> {
+    const TARGET: &str = "GET /hacks/{id}";
+    log::info!(target: TARGET, "Request received");
+
+    let id = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    #[allow(unused_mut)]
+    let mut hack: Hack = models::get_hack(conn, id as i32)?.into();
+    #[cfg(feature = "authorization")]
+    hack.redact(&user_claims);
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(hack))
+}
@@ -1,4 +1,8 @@
-use std::process;
+use std::{
+    collections::BTreeMap,
+    path::{self, Component},
+    process,
+};
 
 use actix_web::{
     delete, get, post, put,
@@ -21,18 +25,43 @@ use serde_json::json;
 use super::{
     contests::Contest,
     err::{Error, Reason},
+    participations::Participation,
+    teams,
 };
 
 use crate::{persistent::models, DbPool};
 
 #[cfg(feature = "authorization")]
 use crate::authorization::{Role, UserClaims};
+use diesel::SqliteConnection;
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::config::{Config, Language, Problem};
+use crate::judge::Message;
+
+mod archive;
 
-use crate::config::Config;
+/// Maximum total size of submitted source files, or answers, in bytes
+pub const MAX_SUBMISSION_SIZE: usize = 1 << 20;
+
+/// Maximum number of files in a multi-file submission
+pub const MAX_SUBMISSION_FILES: usize = 256;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Submission {
+    #[serde(default)]
     pub source_code: String,
+    /// Source files of a multi-file submission, keyed by relative path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<BTreeMap<String, String>>,
+    /// A base64-encoded archive, which is unpacked into `files` on submission
+    #[serde(default, skip_serializing)]
+    pub archive: Option<String>,
+    /// Outputs of each case, for answer-only problems
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answers: Option<Vec<String>>,
+    /// Not needed for answer-only problems
+    #[serde(default)]
     pub language: String,
     pub user_id: u32,
     pub contest_id: u32,
@@ -74,6 +103,43 @@ where
     }
 }
 
+/// How a submission to a contest is made
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Integer)]
+pub enum SubmissionKind {
+    /// During the contest, or outside of any contest
+    Official,
+    /// During a virtual participation after the contest
+    Virtual,
+    /// After the contest, out of the competition
+    Upsolving,
+}
+
+impl ToSql<Integer, Sqlite> for SubmissionKind
+where
+    i32: ToSql<Integer, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(*self as i32);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for SubmissionKind
+where
+    DB: Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(SubmissionKind::Official),
+            1 => Ok(SubmissionKind::Virtual),
+            2 => Ok(SubmissionKind::Upsolving),
+            x => Err(format!("Unrecognized enum variant {x}").into()),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
 #[diesel(sql_type = Integer)]
 pub enum JobResult {
@@ -97,6 +163,12 @@ pub enum JobResult {
     #[serde(rename = "SPJ Error")]
     SpjError,
     Skipped,
+    #[serde(rename = "Output Limit Exceeded")]
+    OutputLimitExceeded,
+    #[serde(rename = "Partially Correct")]
+    PartiallyCorrect,
+    #[serde(rename = "Presentation Error")]
+    PresentationError,
 }
 
 impl ToSql<Integer, Sqlite> for JobResult
@@ -128,6 +200,9 @@ where
             9 => Ok(JobResult::SystemError),
             10 => Ok(JobResult::SpjError),
             11 => Ok(JobResult::Skipped),
+            12 => Ok(JobResult::OutputLimitExceeded),
+            13 => Ok(JobResult::PartiallyCorrect),
+            14 => Ok(JobResult::PresentationError),
             x => Err(format!("Unrecognized enum variant {x}").into()),
         }
     }
@@ -137,11 +212,29 @@ where
 pub struct CaseResult {
     pub id: u32,
     pub result: JobResult,
+    /// CPU time in microseconds
     pub time: u32,
+    /// Wall clock time in microseconds
+    #[serde(default)]
+    pub wall_time: u32,
     pub memory: u32,
     pub info: String,
 }
 
+impl CaseResult {
+    /// Make a case result with nothing measured
+    pub fn new(id: u32, result: JobResult) -> Self {
+        Self {
+            id,
+            result,
+            time: 0,
+            wall_time: 0,
+            memory: 0,
+            info: String::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, AsExpression, FromSqlRow)]
 #[diesel(sql_type = Text)]
 pub struct CaseResults(pub Vec<CaseResult>);
@@ -168,6 +261,129 @@ where
     }
 }
 
+#[derive(Clone, Debug, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct SourceFiles(pub BTreeMap<String, String>);
+
+impl ToSql<Text, Sqlite> for SourceFiles
+where
+    String: ToSql<Text, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(json!(self.0).to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for SourceFiles
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        let s = String::from_sql(bytes)?;
+        let v = serde_json::from_str(&s)?;
+        Ok(SourceFiles(v))
+    }
+}
+
+#[derive(Clone, Debug, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct Answers(pub Vec<String>);
+
+impl ToSql<Text, Sqlite> for Answers
+where
+    String: ToSql<Text, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(json!(self.0).to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for Answers
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        let s = String::from_sql(bytes)?;
+        let v = serde_json::from_str(&s)?;
+        Ok(Answers(v))
+    }
+}
+
+/// Whether a submitted file path stays inside the directory it's extracted to
+pub fn is_safe_path(name: &str) -> bool {
+    !name.is_empty()
+        && path::Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Check the files of a multi-file submission
+fn check_files(files: &BTreeMap<String, String>, lang: &Language) -> Result<(), Error> {
+    if files.is_empty() || files.len() > MAX_SUBMISSION_FILES {
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            format!("A submission must have 1 to {MAX_SUBMISSION_FILES} files"),
+        ));
+    }
+    if files.values().map(|content| content.len()).sum::<usize>() > MAX_SUBMISSION_SIZE {
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            "Submission too large".to_string(),
+        ));
+    }
+    if let Some(name) = files.keys().find(|name| !is_safe_path(name)) {
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            format!("Invalid file name: {name}"),
+        ));
+    }
+    // Without a build command, the main file is compiled as a single-file submission would be
+    if lang.build.is_none() && !files.contains_key(&lang.file_name) {
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            format!("Missing main file: {}", lang.file_name),
+        ));
+    }
+    Ok(())
+}
+
+/// Check the answers of a submission to an answer-only problem
+fn check_answers(submission: &Submission, problem: &Problem) -> Result<(), Error> {
+    if submission.files.is_some() || submission.archive.is_some() {
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            format!("Problem {} only accepts answers", problem.id),
+        ));
+    }
+    match &submission.answers {
+        None => Err(Error::new(
+            Reason::InvalidArgument,
+            format!("Missing answers to problem {}", problem.id),
+        )),
+        Some(answers) if answers.len() != problem.cases.len() => Err(Error::new(
+            Reason::InvalidArgument,
+            format!(
+                "Expected {} answers, found {}",
+                problem.cases.len(),
+                answers.len()
+            ),
+        )),
+        Some(answers)
+            if answers.iter().map(|answer| answer.len()).sum::<usize>() > MAX_SUBMISSION_SIZE =>
+        {
+            Err(Error::new(
+                Reason::InvalidArgument,
+                "Submission too large".to_string(),
+            ))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct Job {
     pub id: u32,
@@ -176,6 +392,10 @@ pub struct Job {
     #[serde(serialize_with = "super::serialize_date_time")]
     pub updated_time: DateTime<Utc>,
     pub submission: Submission,
+    pub kind: SubmissionKind,
+    /// The team the submission is attributed to, if the user is in one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<u32>,
     pub state: JobStatus,
     pub result: JobResult,
     pub score: f64,
@@ -190,11 +410,16 @@ impl From<models::Job> for Job {
             updated_time: job.updated_time.and_local_timezone(Utc).unwrap(),
             submission: Submission {
                 source_code: job.source_code,
+                files: job.source_files.map(|files| files.0),
+                archive: None,
+                answers: job.answers.map(|answers| answers.0),
                 language: job.lang,
                 user_id: job.user_id as u32,
                 contest_id: job.contest_id as u32,
                 problem_id: job.problem_id as u32,
             },
+            kind: job.submission_kind,
+            team_id: job.team_id.map(|team_id| team_id as u32),
             state: job.job_state,
             result: job.result,
             score: job.score,
@@ -203,25 +428,96 @@ impl From<models::Job> for Job {
     }
 }
 
-/// Queue a judge job
-fn queue_job(id: i32, channel: &Channel) -> Result<(), Error> {
+impl Job {
+    /// Hide the results of cases while the contest of the job asks to
+    ///
+    /// Returns whether they are hidden.
+    fn hide_case_results(&mut self, contest: Option<&Contest>) -> bool {
+        let hidden =
+            contest.is_some_and(|contest| contest.hide_case_results && Utc::now() < contest.to);
+        if hidden {
+            // Keep the compilation result, which is all the user needs to fix their code
+            self.cases.truncate(1);
+        }
+        hidden
+    }
+
+    /// Hide the details that an unprivileged user should not see
+    ///
+    /// Only verdicts are shown for cases that are not samples, and no case is shown at all
+    /// while the contest of the job hides case results.
+    #[cfg(feature = "authorization")]
+    fn redact(&mut self, problem: Option<&Problem>, contest: Option<&Contest>) {
+        if self.hide_case_results(contest) {
+            return;
+        }
+        for case in self.cases.iter_mut().skip(1) {
+            let sample = problem
+                .and_then(|problem| problem.cases.get(case.id as usize - 1))
+                .is_some_and(|case| case.sample);
+            if !sample {
+                case.info.clear();
+            }
+        }
+    }
+}
+
+/// Hide the details of jobs that an unprivileged user should not see
+///
+/// Without authorization nobody is told apart, so case results hidden by contests are hidden
+/// from everyone, while the details of every case are shown.
+fn redact_jobs(
+    conn: &mut SqliteConnection,
+    #[cfg(feature = "authorization")] config: &Config,
+    jobs: &mut [Job],
+) -> Result<(), Error> {
+    let mut contests = HashMap::new();
+    for job in jobs {
+        let cid = job.submission.contest_id;
+        let contest = match cid {
+            0 => None,
+            _ => Some(match contests.entry(cid) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(Contest::from(models::get_contest(conn, cid as i32)?))
+                }
+            }),
+        };
+        #[cfg(feature = "authorization")]
+        job.redact(
+            config.get_problem(job.submission.problem_id),
+            contest.as_deref(),
+        );
+        #[cfg(not(feature = "authorization"))]
+        job.hide_case_results(contest.as_deref());
+    }
+    Ok(())
+}
+
+/// Send a message to the judgers
+pub fn publish(message: Message, channel: &Channel) -> Result<(), Error> {
     let exchange = Exchange::direct(channel);
 
     exchange
         .publish(Publish::new(
-            &id.to_ne_bytes(),
+            &message.to_bytes(),
             format!("judger{}", process::id()),
         ))
         .map_err(|err| {
-            log::error!(target: "queue_job", "Failed to publish message: {err}");
+            log::error!(target: "publish", "Failed to publish message: {err}");
             Error::new(Reason::External, "Message queue error".to_string())
         })
 }
 
+/// Queue a judge job
+fn queue_job(id: i32, channel: &Channel) -> Result<(), Error> {
+    publish(Message::Job(id), channel)
+}
+
 #[post("/jobs")]
 /// Create a new submission
 pub async fn new_job(
-    submission: Json<Submission>,
+    mut submission: Json<Submission>,
     config: Data<Config>,
     pool: Data<DbPool>,
     amqp_channel: Data<Channel>,
@@ -242,15 +538,20 @@ pub async fn new_job(
     let pool_cloned = pool.clone();
     let conn = &mut web::block(move || pool_cloned.get()).await??;
 
+    // Answer-only submissions are not written in any language
+    let answer_only = config
+        .get_problem(submission.problem_id)
+        .is_some_and(|problem| problem.is_answer_only());
+
     match config.get_lang(&submission.language) {
-        None => {
+        None if !answer_only => {
             log::info!(target: TARGET, "No such language: {}", submission.language);
             Err(Error::new(
                 Reason::NotFound,
                 format!("No such language: {}", submission.language),
             ))
         }
-        Some(_) => {
+        lang => {
             match config.get_problem(submission.problem_id) {
                 None => {
                     log::info!(target: TARGET, "No such problem: {}", submission.problem_id);
@@ -262,6 +563,61 @@ pub async fn new_job(
                 Some(problem) => {
                     let pid = problem.id;
                     let uid = submission.user_id;
+                    if answer_only {
+                        check_answers(&submission, problem).map_err(|err| {
+                            log::info!(target: TARGET, "Invalid answers: {err}");
+                            err
+                        })?;
+                    } else {
+                        let lang = lang.unwrap();
+                        if submission.answers.is_some() {
+                            log::info!(target: TARGET, "Answers submitted to problem {pid}");
+                            return Err(Error::new(
+                                Reason::InvalidArgument,
+                                format!("Problem {pid} is not answer-only"),
+                            ));
+                        }
+
+                        // Function-implementation problems only accept languages with a grader
+                        if !problem.supports_lang(&submission.language) {
+                            log::info!(
+                                target: TARGET,
+                                "Language {} not supported by problem {pid}",
+                                submission.language
+                            );
+                            return Err(Error::new(
+                                Reason::InvalidArgument,
+                                format!(
+                                    "Language {} not supported by problem {pid}",
+                                    submission.language
+                                ),
+                            ));
+                        }
+
+                        // Unpack the archive and check the submitted files
+                        if let Some(archive) = submission.archive.take() {
+                            if submission.files.is_some() {
+                                log::info!(target: TARGET, "Both files and archive submitted");
+                                return Err(Error::new(
+                                    Reason::InvalidArgument,
+                                    "Only one of files and archive can be submitted".to_string(),
+                                ));
+                            }
+                            submission.files = Some(archive::unpack(&archive)?);
+                        }
+                        if let Some(files) = &submission.files {
+                            check_files(files, lang).map_err(|err| {
+                                log::info!(target: TARGET, "Invalid files: {err}");
+                                err
+                            })?;
+                        } else if submission.source_code.len() > MAX_SUBMISSION_SIZE {
+                            log::info!(target: TARGET, "Submission too large");
+                            return Err(Error::new(
+                                Reason::InvalidArgument,
+                                "Submission too large".to_string(),
+                            ));
+                        }
+                    }
                     log::info!(target: TARGET, "Checking if user exists...");
                     let user_exists = models::does_user_exist(conn, uid as i32)?;
                     if !user_exists {
@@ -273,6 +629,8 @@ pub async fn new_job(
                     }
 
                     let cid = submission.contest_id;
+                    let mut kind = SubmissionKind::Official;
+                    let mut team_id = None;
                     // Check validity when submits to a specific contest
                     if cid != 0 {
                         let contest: Contest = models::get_contest(conn, cid as i32)
@@ -284,13 +642,6 @@ pub async fn new_job(
                                 _ => err,
                             })?
                             .into();
-                        if !contest.user_ids.contains(&uid) {
-                            log::info!(target: TARGET, "User {uid} not in contest {cid}");
-                            return Err(Error::new(
-                                Reason::InvalidArgument,
-                                format!("User {uid} not in contest {cid}"),
-                            ));
-                        }
                         if !contest.problem_ids.contains(&pid) {
                             log::info!(target: TARGET, "Problem {pid} not in contest {cid}");
                             return Err(Error::new(
@@ -307,15 +658,72 @@ pub async fn new_job(
                             ));
                         }
                         if now > contest.to {
-                            log::info!(target: TARGET, "Contest {cid} has already ended");
+                            // Submissions after the contest are accepted in a virtual participation,
+                            // or out of the competition if the contest allows upsolving
+                            let participation =
+                                models::find_participation(conn, cid as i32, uid as i32)?;
+                            let window = participation
+                                .as_ref()
+                                .filter(|participation| {
+                                    participation.participation_kind == SubmissionKind::Virtual
+                                })
+                                .map(|participation| Participation::new(participation, &contest));
+                            if window.is_some_and(|window| now <= window.to) {
+                                kind = SubmissionKind::Virtual;
+                            } else if !contest.allow_upsolving {
+                                log::info!(target: TARGET, "Contest {cid} has already ended");
+                                return Err(Error::new(
+                                    Reason::InvalidArgument,
+                                    format!("Contest {cid} has already ended"),
+                                ));
+                            } else if participation.is_none() && !contest.user_ids.contains(&uid) {
+                                log::info!(target: TARGET, "User {uid} not in contest {cid}");
+                                return Err(Error::new(
+                                    Reason::InvalidArgument,
+                                    format!("User {uid} not in contest {cid}"),
+                                ));
+                            } else {
+                                kind = SubmissionKind::Upsolving;
+                            }
+                        } else if !contest.user_ids.contains(&uid) {
+                            log::info!(target: TARGET, "User {uid} not in contest {cid}");
                             return Err(Error::new(
                                 Reason::InvalidArgument,
-                                format!("Contest {cid} has already ended"),
+                                format!("User {uid} not in contest {cid}"),
                             ));
+                        } else if contest.duration.is_some() {
+                            // Each user competes in their own window from the time they started
+                            let Some(participation) =
+                                models::find_participation(conn, cid as i32, uid as i32)?
+                            else {
+                                log::info!(target: TARGET, "User {uid} hasn't started contest {cid}");
+                                return Err(Error::new(
+                                    Reason::InvalidArgument,
+                                    format!("User {uid} hasn't started contest {cid}"),
+                                ));
+                            };
+                            if now > Participation::new(&participation, &contest).to {
+                                log::info!(target: TARGET, "Time is up for user {uid} in contest {cid}");
+                                return Err(Error::new(
+                                    Reason::InvalidArgument,
+                                    format!("Time is up for user {uid} in contest {cid}"),
+                                ));
+                            }
+                        }
+                        // Submissions are shared with the team, except in virtual participations
+                        if kind != SubmissionKind::Virtual {
+                            team_id = teams::find_team(conn, cid, uid)?.map(|team| team.id);
                         }
-                        if models::get_submission_count(conn, uid as i32, pid as i32, cid as i32)?
-                            as u32
-                            >= contest.submission_limit
+                        // Upsolving is practice, so there is no limit on submissions
+                        let scope = models::SubmissionScope {
+                            team: team_id.map(|team_id| team_id as i32),
+                            ..models::SubmissionScope::all(kind)
+                        };
+                        if kind != SubmissionKind::Upsolving
+                            && models::get_submission_count(
+                                conn, uid as i32, pid as i32, cid as i32, &scope,
+                            )? as u32
+                                >= contest.submission_limit_of(pid)
                         {
                             log::info!(target: TARGET, "Submission limit exceeded");
                             return Err(Error::new(
@@ -343,6 +751,8 @@ pub async fn new_job(
                         created_time: created,
                         updated_time: created,
                         submission: submission.clone(),
+                        kind,
+                        team_id,
                         state: JobStatus::Queueing,
                         result: JobResult::Waiting,
                         score: 0.0,
@@ -351,6 +761,7 @@ pub async fn new_job(
                                 id: id as u32,
                                 result: JobResult::Waiting,
                                 time: 0,
+                                wall_time: 0,
                                 memory: 0,
                                 info: "".to_string(),
                             })
@@ -383,35 +794,57 @@ type JobFilter = models::JobFilter;
 pub async fn get_jobs(
     filter: Query<JobFilter>,
     pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] config: Data<Config>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
 ) -> Result<Json<Vec<Job>>, Error> {
     const TARGET: &str = "GET /jobs";
     log::info!(target: TARGET, "Request received");
 
     let filtered_jobs = web::block(move || {
         let mut conn = pool.get()?;
-        models::get_jobs(&mut conn, filter.into_inner())
+        let mut jobs: Vec<Job> = models::get_jobs(&mut conn, filter.into_inner())?
+            .into_iter()
+            .map(|job| job.into())
+            .collect();
+        #[cfg(feature = "authorization")]
+        if user_claims.role < Role::Author {
+            redact_jobs(&mut conn, &config, &mut jobs)?;
+        }
+        #[cfg(not(feature = "authorization"))]
+        redact_jobs(&mut conn, &mut jobs)?;
+        Ok::<_, Error>(jobs)
     })
     .await??;
 
     log::info!(target: TARGET, "Request done");
-    Ok(Json(
-        filtered_jobs.into_iter().map(|job| job.into()).collect(),
-    ))
+    Ok(Json(filtered_jobs))
 }
 
 #[get("/jobs/{id}")]
-pub async fn get_job(id: Path<i32>, pool: Data<DbPool>) -> Result<Json<Job>, Error> {
+pub async fn get_job(
+    id: Path<i32>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] config: Data<Config>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Job>, Error> {
     const TARGET: &str = "GET /jobs/{id}";
     log::info!(target: TARGET, "Request received");
 
     let id = id.into_inner();
     let job = web::block(move || {
         let mut conn = pool.get()?;
-        models::get_job(&mut conn, id)
+        let mut job: Job = models::get_job(&mut conn, id)?.into();
+        #[cfg(feature = "authorization")]
+        if user_claims.role < Role::Author {
+            redact_jobs(&mut conn, &config, std::slice::from_mut(&mut job))?;
+        }
+        #[cfg(not(feature = "authorization"))]
+        redact_jobs(&mut conn, std::slice::from_mut(&mut job))?;
+        Ok::<_, Error>(job)
     })
     .await??;
     log::info!(target: TARGET, "Request done");
-    Ok(Json(job.into()))
+    Ok(Json(job))
 }
 
 #[put("/jobs/{id}")]
@@ -470,6 +903,7 @@ pub async fn rejudge_job(
                     id: id as u32,
                     result: JobResult::Waiting,
                     time: 0,
+                    wall_time: 0,
                     memory: 0,
                     info: "".to_string(),
                 })
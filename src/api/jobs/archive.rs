@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use flate2::read::GzDecoder;
+
+use super::{MAX_SUBMISSION_FILES, MAX_SUBMISSION_SIZE};
+use crate::api::err::{Error, Reason};
+
+/// Magic number at the beginning of a zip archive
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Magic number at the beginning of a gzip stream
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+fn invalid(message: String) -> Error {
+    Error::new(Reason::InvalidArgument, message)
+}
+
+/// Read a file from the archive, counting its size towards the total limit
+fn read_file(
+    files: &mut BTreeMap<String, String>,
+    name: String,
+    reader: impl Read,
+    total: &mut usize,
+) -> Result<(), Error> {
+    if files.len() >= MAX_SUBMISSION_FILES {
+        return Err(invalid("Too many files in archive".to_string()));
+    }
+
+    // Never read past the limit, in case the archive lies about the size
+    let mut buf = vec![];
+    reader
+        .take((MAX_SUBMISSION_SIZE - *total) as u64 + 1)
+        .read_to_end(&mut buf)
+        .map_err(|err| invalid(format!("Unable to read {name} from archive: {err}")))?;
+    *total += buf.len();
+    if *total > MAX_SUBMISSION_SIZE {
+        return Err(invalid("Submission too large".to_string()));
+    }
+
+    let content =
+        String::from_utf8(buf).map_err(|_| invalid(format!("File {name} is not UTF-8 text")))?;
+    files.insert(name, content);
+    Ok(())
+}
+
+/// Unpack a zip archive
+fn unpack_zip(bytes: &[u8]) -> Result<BTreeMap<String, String>, Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|err| invalid(format!("Invalid zip archive: {err}")))?;
+
+    let mut files = BTreeMap::new();
+    let mut total = 0;
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|err| invalid(format!("Invalid zip archive: {err}")))?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        read_file(&mut files, name, file, &mut total)?;
+    }
+    Ok(files)
+}
+
+/// Unpack a tar archive
+fn unpack_tar(reader: impl Read) -> Result<BTreeMap<String, String>, Error> {
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive
+        .entries()
+        .map_err(|err| invalid(format!("Invalid tar archive: {err}")))?;
+
+    let mut files = BTreeMap::new();
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry.map_err(|err| invalid(format!("Invalid tar archive: {err}")))?;
+        match entry.header().entry_type() {
+            tar::EntryType::Regular => (),
+            tar::EntryType::Directory => continue,
+            // Links and devices could point outside the working directory
+            _ => {
+                return Err(invalid(
+                    "Only regular files are allowed in archive".to_string(),
+                ))
+            }
+        }
+        let name = entry
+            .path()
+            .ok()
+            .and_then(|path| path.to_str().map(|s| s.to_string()))
+            .ok_or_else(|| invalid("Invalid file name in archive".to_string()))?;
+        read_file(&mut files, name, entry, &mut total)?;
+    }
+    Ok(files)
+}
+
+/// Unpack a base64-encoded zip, tar or gzipped tar archive into named files
+///
+/// File names are not checked here, which is left to the caller.
+pub fn unpack(archive: &str) -> Result<BTreeMap<String, String>, Error> {
+    let bytes = base64::decode(archive)
+        .map_err(|err| invalid(format!("Archive is not valid base64: {err}")))?;
+
+    if bytes.starts_with(ZIP_MAGIC) {
+        unpack_zip(&bytes)
+    } else if bytes.starts_with(GZIP_MAGIC) {
+        unpack_tar(GzDecoder::new(bytes.as_slice()))
+    } else {
+        unpack_tar(bytes.as_slice())
+    }
+}
@@ -0,0 +1,196 @@
+use actix_web::{
+    post,
+    web::{self, Data, Json, Path},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{api::jobs::SubmissionKind, persistent::models, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::UserClaims;
+
+use super::{
+    contests::{load_contest, Contest},
+    err::{Error, Reason},
+};
+
+/// A personal window of a user in a contest, or in a virtual participation after it
+#[derive(Serialize)]
+pub struct Participation {
+    pub contest_id: u32,
+    pub user_id: u32,
+    pub kind: SubmissionKind,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub from: DateTime<Utc>,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub to: DateTime<Utc>,
+}
+
+impl Participation {
+    pub fn new(participation: &models::Participation, contest: &Contest) -> Self {
+        let from = participation.start_time.and_local_timezone(Utc).unwrap();
+        let to = from + contest.length();
+        Self {
+            contest_id: participation.contest_id as u32,
+            user_id: participation.user_id as u32,
+            kind: participation.participation_kind,
+            from,
+            // A personal window can't outlast the contest itself
+            to: match participation.participation_kind {
+                SubmissionKind::Official => to.min(contest.to),
+                _ => to,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ParticipateForm {
+    pub user_id: u32,
+}
+
+#[post("/contests/{id}/virtual")]
+pub async fn start_virtual(
+    id: Path<u32>,
+    form: Json<ParticipateForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Participation>, Error> {
+    const TARGET: &str = "POST /contests/{id}/virtual";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only participate for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to participate on behalf of others".to_string(),
+        ));
+    }
+
+    let (cid, uid) = (id.into_inner(), form.user_id);
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let participation = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        if !models::does_user_exist(conn, uid as i32)? {
+            log::info!(target: TARGET, "No such user: {uid}");
+            return Err(Error::new(Reason::NotFound, format!("No such user: {uid}")));
+        }
+
+        let now = Utc::now();
+        if now <= contest.to {
+            log::info!(target: TARGET, "Contest {cid} hasn't yet ended");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} hasn't yet ended"),
+            ));
+        }
+        // Those who took part in the contest or upsolved it already know the problems
+        let kinds = [SubmissionKind::Official, SubmissionKind::Upsolving];
+        if models::has_submissions(conn, uid as i32, cid as i32, &kinds)? {
+            log::info!(target: TARGET, "User {uid} took part in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("User {uid} took part in contest {cid}"),
+            ));
+        }
+        if models::find_participation(conn, cid as i32, uid as i32)?.is_some() {
+            log::info!(target: TARGET, "User {uid} already participated in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("User {uid} already participated in contest {cid}"),
+            ));
+        }
+
+        let participation = models::new_participation(
+            conn,
+            models::Participation {
+                contest_id: cid as i32,
+                user_id: uid as i32,
+                start_time: now.naive_utc(),
+                participation_kind: SubmissionKind::Virtual,
+            },
+        )?;
+        Ok(Participation::new(&participation, &contest))
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(participation))
+}
+
+#[post("/contests/{id}/start")]
+pub async fn start(
+    id: Path<u32>,
+    form: Json<ParticipateForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Participation>, Error> {
+    const TARGET: &str = "POST /contests/{id}/start";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only start for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to start on behalf of others".to_string(),
+        ));
+    }
+
+    let (cid, uid) = (id.into_inner(), form.user_id);
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let participation = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        if !models::does_user_exist(conn, uid as i32)? {
+            log::info!(target: TARGET, "No such user: {uid}");
+            return Err(Error::new(Reason::NotFound, format!("No such user: {uid}")));
+        }
+        if !contest.user_ids.contains(&uid) {
+            log::info!(target: TARGET, "User {uid} not in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("User {uid} not in contest {cid}"),
+            ));
+        }
+
+        if contest.duration.is_none() {
+            log::info!(target: TARGET, "Contest {cid} has no personal windows");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} has no personal windows"),
+            ));
+        }
+        let now = Utc::now();
+        if now < contest.from || now > contest.to {
+            log::info!(target: TARGET, "Contest {cid} is not running");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} is not running"),
+            ));
+        }
+        if models::find_participation(conn, cid as i32, uid as i32)?.is_some() {
+            log::info!(target: TARGET, "User {uid} already started contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("User {uid} already started contest {cid}"),
+            ));
+        }
+
+        let participation = models::new_participation(
+            conn,
+            models::Participation {
+                contest_id: cid as i32,
+                user_id: uid as i32,
+                start_time: now.naive_utc(),
+                participation_kind: SubmissionKind::Official,
+            },
+        )?;
+        Ok(Participation::new(&participation, &contest))
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(participation))
+}
@@ -0,0 +1,181 @@
+use std::{collections::HashSet, fs};
+
+use actix_web::{
+    get,
+    web::{self, Data, Json, Path},
+};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::{
+    api::{contests::Contest, err::Reason},
+    config::{self, Config, ProblemType},
+    persistent::models,
+    DbPool,
+};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::err::Error;
+
+/// Input and answer of a sample case
+#[derive(Serialize)]
+pub struct Sample {
+    pub input: String,
+    pub output: String,
+}
+
+#[derive(Serialize)]
+pub struct Problem {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: ProblemType,
+    /// The largest CPU time limit of the cases in microseconds, 0 for unlimited
+    pub time_limit: u32,
+    /// The largest memory limit of the cases in bytes, 0 for unlimited
+    pub memory_limit: u32,
+    pub tags: Vec<String>,
+    pub difficulty: Option<u32>,
+    pub author: Option<String>,
+    pub source: Option<String>,
+    /// Statement in Markdown, only given when getting a single problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement: Option<String>,
+    /// Sample cases, only given when getting a single problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samples: Option<Vec<Sample>>,
+}
+
+/// Get the largest of the limits, where 0 stands for unlimited
+pub fn max_limit<T: Copy + Default + Ord>(limits: impl Iterator<Item = T>) -> T {
+    let mut max = T::default();
+    for limit in limits {
+        if limit == T::default() {
+            return limit;
+        }
+        max = max.max(limit);
+    }
+    max
+}
+
+impl From<&config::Problem> for Problem {
+    fn from(problem: &config::Problem) -> Self {
+        Self {
+            id: problem.id,
+            name: problem.name.clone(),
+            typ: problem.typ.clone(),
+            time_limit: max_limit(problem.cases.iter().map(|case| case.time_limit)),
+            memory_limit: max_limit(problem.cases.iter().map(|case| case.memory_limit)),
+            tags: problem.tags.clone(),
+            difficulty: problem.difficulty,
+            author: problem.author.clone(),
+            source: problem.source.clone(),
+            statement: None,
+            samples: None,
+        }
+    }
+}
+
+/// Get the problems in contests that have not yet begun
+fn get_hidden_problems(pool: &DbPool) -> Result<HashSet<u32>, Error> {
+    let mut conn = pool.get()?;
+    let now = Utc::now().naive_utc();
+    Ok(models::get_contests(&mut conn)?
+        .into_iter()
+        .filter(|contest| now < contest.contest_from)
+        .flat_map(|contest| Contest::from(contest).problem_ids)
+        .collect())
+}
+
+#[get("/problems")]
+pub async fn get_problems(
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<Problem>>, Error> {
+    const TARGET: &str = "GET /problems";
+    log::info!(target: TARGET, "Request received");
+
+    // Problems in contests that have not yet begun are hidden from users, or from everyone
+    // without authorization
+    #[cfg(feature = "authorization")]
+    let hidden = match user_claims.role < Role::Author {
+        true => web::block(move || get_hidden_problems(&pool)).await??,
+        false => HashSet::new(),
+    };
+    #[cfg(not(feature = "authorization"))]
+    let hidden = web::block(move || get_hidden_problems(&pool)).await??;
+
+    let problems = config
+        .problems
+        .iter()
+        .filter(|problem| !hidden.contains(&problem.id))
+        .map(Problem::from)
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(problems))
+}
+
+#[get("/problems/{id}")]
+pub async fn get_problem(
+    id: Path<u32>,
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Problem>, Error> {
+    const TARGET: &str = "GET /problems/{id}";
+    log::info!(target: TARGET, "Request received");
+
+    let id = id.into_inner();
+    let not_found = || {
+        log::info!(target: TARGET, "No such problem: {id}");
+        Error::new(Reason::NotFound, format!("Problem {id} not found."))
+    };
+    let problem = config.get_problem(id).ok_or_else(not_found)?.clone();
+
+    // Hidden problems are indistinguishable from nonexistent ones
+    #[cfg(feature = "authorization")]
+    let privileged = user_claims.role >= Role::Author;
+    #[cfg(not(feature = "authorization"))]
+    let privileged = false;
+    if !privileged
+        && web::block(move || get_hidden_problems(&pool))
+            .await??
+            .contains(&id)
+    {
+        return Err(not_found());
+    }
+
+    // Statements and samples are read from files, which would block
+    let problem = web::block(move || {
+        let read = |path: &std::path::Path| {
+            fs::read_to_string(path).map_err(|err| {
+                log::error!(target: TARGET, "Unable to read {}: {err}", path.display());
+                Error::new(Reason::Internal, format!("Unable to read problem {id}"))
+            })
+        };
+        let statement = match &problem.statement {
+            Some(path) => read(path)?,
+            None => String::new(),
+        };
+        let mut samples = vec![];
+        for case in problem.cases.iter().filter(|case| case.sample) {
+            samples.push(Sample {
+                input: read(&case.input_file)?,
+                output: read(&case.answer_file)?,
+            });
+        }
+        Ok::<_, Error>(Problem {
+            statement: Some(statement),
+            samples: Some(samples),
+            ..Problem::from(&problem)
+        })
+    })
+    .await??;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(problem))
+}
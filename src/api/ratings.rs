@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::{
+    get, post,
+    web::{self, Data, Json, Path, Query},
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::persistent::models::{self, User};
+use crate::rating::{self, Contestant, INITIAL_RATING};
+use crate::{config::Config, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::{self, load_contest, RankingRule},
+    err::{Error, Reason},
+    jobs::SubmissionKind,
+};
+
+/// The rank and rating change of a user in a finalized contest
+#[derive(Serialize)]
+pub struct RatingChange {
+    pub contest_id: u32,
+    pub user_id: u32,
+    pub rank: u32,
+    pub old_rating: i32,
+    pub new_rating: i32,
+}
+
+impl From<models::Rating> for RatingChange {
+    fn from(rating: models::Rating) -> Self {
+        Self {
+            contest_id: rating.contest_id as u32,
+            user_id: rating.user_id as u32,
+            rank: rating.user_rank as u32,
+            old_rating: rating.old_rating,
+            new_rating: rating.new_rating,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UserRating {
+    pub user: User,
+    /// Current rating, which is the initial rating before any rated contest
+    pub rating: i32,
+    /// Rating changes in the order the contests ended
+    pub history: Vec<RatingChange>,
+}
+
+/// Order finalized contests by the time they ended, so that ratings are replayed in that order
+fn contest_order(conn: &mut SqliteConnection, cids: &[i32]) -> Result<Vec<i32>, Error> {
+    let mut contests = cids
+        .iter()
+        .map(|cid| Ok((models::get_contest(conn, *cid)?.contest_to, *cid)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    contests.sort();
+    Ok(contests.into_iter().map(|(_, cid)| cid).collect())
+}
+
+/// Recompute the ratings in all finalized contests from the initial rating
+///
+/// Later contests are affected when an earlier one is finalized again, e.g. after a rejudge, so
+/// all of them are replayed with the ranks stored when they were finalized.
+fn replay(conn: &mut SqliteConnection) -> Result<(), Error> {
+    let mut contests = BTreeMap::<i32, Vec<models::Rating>>::new();
+    for rating in models::get_ratings(conn)? {
+        contests.entry(rating.contest_id).or_default().push(rating);
+    }
+    let order = contest_order(conn, &contests.keys().copied().collect::<Vec<_>>())?;
+
+    let mut current = HashMap::<i32, i32>::new();
+    for cid in order {
+        let ratings = contests.remove(&cid).unwrap();
+        let contestants: Vec<Contestant> = ratings
+            .iter()
+            .map(|rating| Contestant {
+                rating: *current.get(&rating.user_id).unwrap_or(&INITIAL_RATING),
+                rank: rating.user_rank as u32,
+            })
+            .collect();
+        let changes = rating::rating_changes(&contestants);
+        for ((mut rating, contestant), change) in ratings.into_iter().zip(contestants).zip(changes)
+        {
+            current.insert(rating.user_id, contestant.rating + change);
+            if (rating.old_rating, rating.new_rating)
+                != (contestant.rating, contestant.rating + change)
+            {
+                rating.old_rating = contestant.rating;
+                rating.new_rating = contestant.rating + change;
+                models::update_rating(conn, rating)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[post("/contests/{id}/finalize")]
+pub async fn finalize(
+    id: Path<u32>,
+    rule: Query<RankingRule>,
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<RatingChange>>, Error> {
+    const TARGET: &str = "POST /contests/{id}/finalize";
+    log::info!(target: TARGET, "Request received");
+
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You have no permission to access this service".to_string(),
+        ));
+    }
+
+    let cid = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let ratings = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        if !contest.rated {
+            log::info!(target: TARGET, "Contest {cid} is unrated");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} is unrated"),
+            ));
+        }
+        if Utc::now() <= contest.to {
+            log::info!(target: TARGET, "Contest {cid} hasn't ended");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} hasn't ended"),
+            ));
+        }
+
+        // Only those who made a submission in the contest are rated, and members of a team
+        // share the rank of the team
+        let mut ratings = vec![];
+        for item in contests::rank_list(conn, &config, cid, rule.into_inner())? {
+            if item.kind != SubmissionKind::Official {
+                continue;
+            }
+            let members = match item.team {
+                Some(team) => team.members,
+                None => item.user.into_iter().collect(),
+            };
+            let mut active = false;
+            for member in &members {
+                active |= models::has_submissions(
+                    conn,
+                    member.id,
+                    cid as i32,
+                    &[SubmissionKind::Official],
+                )?;
+            }
+            if !active {
+                continue;
+            }
+            for member in members {
+                ratings.push(models::Rating {
+                    contest_id: cid as i32,
+                    user_id: member.id,
+                    user_rank: item.rank as i32,
+                    old_rating: INITIAL_RATING,
+                    new_rating: INITIAL_RATING,
+                });
+            }
+        }
+        models::set_ratings(conn, cid as i32, ratings)?;
+        replay(conn)?;
+
+        Ok(models::get_ratings(conn)?
+            .into_iter()
+            .filter(|rating| rating.contest_id == cid as i32)
+            .map(RatingChange::from)
+            .collect())
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(ratings))
+}
+
+#[get("/users/{id}/rating")]
+pub async fn get_user_rating(id: Path<u32>, pool: Data<DbPool>) -> Result<Json<UserRating>, Error> {
+    const TARGET: &str = "GET /users/{id}/rating";
+    log::info!(target: TARGET, "Request received");
+
+    let uid = id.into_inner() as i32;
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let user = models::get_user(conn, uid)?;
+    let mut ratings: HashMap<i32, models::Rating> = models::get_user_ratings(conn, uid)?
+        .into_iter()
+        .map(|rating| (rating.contest_id, rating))
+        .collect();
+    let order = contest_order(conn, &ratings.keys().copied().collect::<Vec<_>>())?;
+    let history: Vec<RatingChange> = order
+        .into_iter()
+        .map(|cid| ratings.remove(&cid).unwrap().into())
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(UserRating {
+        user,
+        rating: history
+            .last()
+            .map_or(INITIAL_RATING, |change| change.new_rating),
+        history,
+    }))
+}
@@ -0,0 +1,306 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Data, Json, Path},
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+    backend::{self, Backend},
+    deserialize::FromSql,
+    prelude::*,
+    serialize::{IsNull, Output, ToSql},
+    sql_types::Integer,
+    sqlite::Sqlite,
+    AsExpression, FromSqlRow,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{persistent::models, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::{load_contest, Contest},
+    err::{Error, Reason},
+    teams,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Integer)]
+pub enum RegistrationState {
+    /// Waiting for the approval of an author
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ToSql<Integer, Sqlite> for RegistrationState
+where
+    i32: ToSql<Integer, Sqlite>,
+{
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Sqlite>) -> diesel::serialize::Result {
+        out.set_value(*self as i32);
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for RegistrationState
+where
+    DB: Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(RegistrationState::Pending),
+            1 => Ok(RegistrationState::Approved),
+            2 => Ok(RegistrationState::Rejected),
+            x => Err(format!("Unrecognized enum variant {x}").into()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Registration {
+    pub contest_id: u32,
+    pub user_id: u32,
+    pub state: RegistrationState,
+    #[serde(serialize_with = "super::serialize_date_time")]
+    pub created_time: DateTime<Utc>,
+}
+
+impl From<models::Registration> for Registration {
+    fn from(registration: models::Registration) -> Self {
+        Self {
+            contest_id: registration.contest_id as u32,
+            user_id: registration.user_id as u32,
+            state: registration.registration_state,
+            created_time: registration.created_time.and_local_timezone(Utc).unwrap(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RegisterForm {
+    pub user_id: u32,
+    /// Invite code of the contest
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewForm {
+    pub state: RegistrationState,
+}
+
+/// Add a user to a contest, or remove it, without touching anything else of the contest
+///
+/// A user removed from the contest leaves their team as well.
+fn set_member(
+    conn: &mut SqliteConnection,
+    contest: &Contest,
+    uid: u32,
+    member: bool,
+) -> Result<(), Error> {
+    let cid = contest.id.unwrap();
+    let mut user_ids = contest.user_ids.clone();
+    user_ids.retain(|&id| id != uid);
+    if member {
+        user_ids.push(uid);
+    } else if let Some(mut team) = teams::find_team(conn, cid, uid)? {
+        team.member_ids.retain(|&id| id != uid);
+        models::set_team_members(conn, team.into())?;
+    }
+    models::set_contest_users(conn, cid as i32, &user_ids)
+}
+
+#[post("/contests/{id}/register")]
+pub async fn register(
+    id: Path<u32>,
+    form: Json<RegisterForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Registration>, Error> {
+    const TARGET: &str = "POST /contests/{id}/register";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only register for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to register on behalf of others".to_string(),
+        ));
+    }
+
+    let (cid, uid) = (id.into_inner(), form.user_id);
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let registration = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        if !models::does_user_exist(conn, uid as i32)? {
+            log::info!(target: TARGET, "No such user: {uid}");
+            return Err(Error::new(Reason::NotFound, format!("No such user: {uid}")));
+        }
+
+        let Some(rule) = &contest.registration else {
+            log::info!(target: TARGET, "Contest {cid} is not open for registration");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} is not open for registration"),
+            ));
+        };
+        let now = Utc::now();
+        if now < rule.from || now > rule.to {
+            log::info!(target: TARGET, "Registration for contest {cid} is closed");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Registration for contest {cid} is closed"),
+            ));
+        }
+        if rule.code.is_some() && rule.code != form.code {
+            log::info!(target: TARGET, "Wrong invite code for contest {cid}");
+            return Err(Error::new(
+                Reason::Forbidden,
+                format!("Wrong invite code for contest {cid}"),
+            ));
+        }
+        if contest.user_ids.contains(&uid)
+            || models::find_registration(conn, cid as i32, uid as i32)?.is_some()
+        {
+            log::info!(target: TARGET, "User {uid} already registered for contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("User {uid} already registered for contest {cid}"),
+            ));
+        }
+
+        let state = match rule.approval {
+            true => RegistrationState::Pending,
+            false => RegistrationState::Approved,
+        };
+        if state == RegistrationState::Approved {
+            set_member(conn, &contest, uid, true)?;
+        }
+        let registration = Registration {
+            contest_id: cid,
+            user_id: uid,
+            state,
+            created_time: now,
+        };
+        models::new_registration(conn, registration.into())
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(registration.into()))
+}
+
+#[delete("/contests/{id}/register")]
+pub async fn withdraw(
+    id: Path<u32>,
+    form: Json<RegisterForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Registration>, Error> {
+    const TARGET: &str = "DELETE /contests/{id}/register";
+    log::info!(target: TARGET, "Request received");
+
+    // You can only withdraw for yourself
+    #[cfg(feature = "authorization")]
+    if form.user_id != user_claims.id {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to withdraw on behalf of others".to_string(),
+        ));
+    }
+
+    let (cid, uid) = (id.into_inner(), form.user_id);
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let registration = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        let registration = models::get_registration(conn, cid as i32, uid as i32)?;
+
+        // Submissions made in the contest would be left without a participant
+        if Utc::now() >= contest.from {
+            log::info!(target: TARGET, "Contest {cid} has already begun");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} has already begun"),
+            ));
+        }
+
+        if registration.registration_state == RegistrationState::Approved {
+            set_member(conn, &contest, uid, false)?;
+        }
+        models::delete_registration(conn, cid as i32, uid as i32)?;
+        Ok::<_, Error>(registration)
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(registration.into()))
+}
+
+#[get("/contests/{id}/registrations")]
+pub async fn get_registrations(
+    id: Path<u32>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Vec<Registration>>, Error> {
+    const TARGET: &str = "GET /contests/{id}/registrations";
+    log::info!(target: TARGET, "Request received");
+
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You have no permission to access this service".to_string(),
+        ));
+    }
+
+    let cid = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    load_contest(conn, TARGET, cid)?;
+    let registrations = models::get_registrations(conn, cid as i32)?
+        .into_iter()
+        .map(|registration| registration.into())
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(registrations))
+}
+
+#[put("/contests/{id}/registrations/{user_id}")]
+pub async fn review_registration(
+    path: Path<(u32, u32)>,
+    form: Json<ReviewForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Registration>, Error> {
+    const TARGET: &str = "PUT /contests/{id}/registrations/{user_id}";
+    log::info!(target: TARGET, "Request received");
+
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You have no permission to access this service".to_string(),
+        ));
+    }
+
+    let (cid, uid) = path.into_inner();
+    let state = form.into_inner().state;
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let registration = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+        let mut registration = models::get_registration(conn, cid as i32, uid as i32)?;
+
+        set_member(conn, &contest, uid, state == RegistrationState::Approved)?;
+        registration.registration_state = state;
+        models::update_registration(conn, registration)
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(registration.into()))
+}
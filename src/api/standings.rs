@@ -0,0 +1,384 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::{
+    get,
+    web::{self, Data, Path, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::persistent::models::{self, JobFilter};
+use crate::{config::Config, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::{self, load_contest, Contest, RankingRule},
+    err::{Error, Reason},
+    jobs::{JobResult, JobStatus, SubmissionKind},
+    participations::Participation,
+    problems::max_limit,
+    teams,
+};
+
+/// Penalty in minutes of a rejected attempt, as in ICPC contests
+const PENALTY_TIME: u32 = 20;
+
+/// Judgement types of the event feed, with whether they are accepted and count as an attempt
+const JUDGEMENT_TYPES: [(&str, &str, bool, bool); 8] = [
+    ("AC", "Accepted", true, false),
+    ("WA", "Wrong Answer", false, true),
+    ("TLE", "Time Limit Exceeded", false, true),
+    ("MLE", "Memory Limit Exceeded", false, true),
+    ("RTE", "Run-Time Error", false, true),
+    ("OLE", "Output Limit Exceeded", false, true),
+    ("CE", "Compiler Error", false, false),
+    ("JE", "Judging Error", false, false),
+];
+
+/// Check that a contest can be exported, which only the privileged can do before it ends
+fn check_ended(target: &str, id: u32, contest: &Contest, privileged: bool) -> Result<(), Error> {
+    if !privileged && Utc::now() < contest.to {
+        log::info!(target: target, "Contest {id} has not ended");
+        return Err(Error::new(
+            Reason::Forbidden,
+            format!("Contest {id} can't be exported before it ends"),
+        ));
+    }
+    Ok(())
+}
+
+/// Quote a field of a CSV file if needed
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[get("/contests/{id}/ranklist.csv")]
+pub async fn get_rank_list_csv(
+    id: Path<u32>,
+    rule: Query<RankingRule>,
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<HttpResponse, Error> {
+    const TARGET: &str = "GET /contests/{id}/ranklist.csv";
+    log::info!(target: TARGET, "Request received");
+
+    let id = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    // Before a contest ends, only authors can export its standings, and nobody can without
+    // authorization
+    #[cfg(feature = "authorization")]
+    let privileged = user_claims.role >= Role::Author;
+    #[cfg(not(feature = "authorization"))]
+    let privileged = false;
+
+    // One column for each problem, in the same order as the scores in the rank list
+    let labels: Vec<String> = if id == 0 {
+        config
+            .problems
+            .iter()
+            .map(|problem| problem.id.to_string())
+            .collect()
+    } else {
+        let contest = load_contest(conn, TARGET, id)?;
+        check_ended(TARGET, id, &contest, privileged)?;
+        contest
+            .problem_ids
+            .iter()
+            .filter(|pid| config.get_problem(**pid).is_some())
+            .map(|pid| contest.label_of(*pid))
+            .collect()
+    };
+    let rank_list = contests::rank_list(conn, &config, id, rule.into_inner())?;
+    let hacks = rank_list.iter().any(|item| item.hack_score.is_some());
+
+    let mut header = vec!["rank".to_string(), "name".to_string(), "kind".to_string()];
+    header.extend(labels);
+    if hacks {
+        header.push("hack_score".to_string());
+    }
+    header.push("total".to_string());
+
+    let mut rows = vec![header];
+    for item in rank_list {
+        let name = match (&item.team, &item.user) {
+            (Some(team), _) => team.name.clone(),
+            (None, Some(user)) => user.user_name.clone(),
+            (None, None) => String::new(),
+        };
+        let total = item.scores.iter().sum::<f64>() + item.hack_score.unwrap_or_default();
+        let mut row = vec![item.rank.to_string(), name, format!("{:?}", item.kind)];
+        row.extend(item.scores.iter().map(|score| score.to_string()));
+        if hacks {
+            row.push(item.hack_score.unwrap_or_default().to_string());
+        }
+        row.push(total.to_string());
+        rows.push(row);
+    }
+    let body: String = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            fields.join(",") + "\r\n"
+        })
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .body(body))
+}
+
+/// Format a time as in the ICPC contest API
+fn abs_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Format a duration as in the ICPC contest API, e.g. `5:00:00.000`
+fn rel_time(duration: Duration) -> String {
+    let ms = duration.num_milliseconds();
+    let sign = if ms < 0 { "-" } else { "" };
+    let ms = ms.abs();
+    format!(
+        "{sign}{}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// The judgement type of the result of a finished submission
+fn judgement_type(result: JobResult) -> &'static str {
+    match result {
+        JobResult::Accepted => "AC",
+        JobResult::WrongAnswer | JobResult::PresentationError | JobResult::PartiallyCorrect => "WA",
+        JobResult::TimeLimitExceeded => "TLE",
+        JobResult::MemoryLimitExceeded => "MLE",
+        JobResult::RuntimeError => "RTE",
+        JobResult::OutputLimitExceeded => "OLE",
+        JobResult::CompilationError => "CE",
+        _ => "JE",
+    }
+}
+
+/// Events of the ICPC contest API event feed, in the 2020-03 format
+#[derive(Default)]
+struct EventFeed {
+    events: Vec<Value>,
+}
+
+impl EventFeed {
+    fn create(&mut self, typ: &str, data: Value) {
+        self.events.push(json!({
+            "id": (self.events.len() + 1).to_string(),
+            "type": typ,
+            "op": "create",
+            "data": data,
+        }));
+    }
+}
+
+#[get("/contests/{id}/event-feed")]
+pub async fn get_event_feed(
+    id: Path<u32>,
+    config: Data<Config>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<HttpResponse, Error> {
+    const TARGET: &str = "GET /contests/{id}/event-feed";
+    log::info!(target: TARGET, "Request received");
+
+    let cid = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    if cid == 0 {
+        log::info!(target: TARGET, "No event feed for contest 0");
+        return Err(Error::new(
+            Reason::InvalidArgument,
+            "Contest 0 has no event feed".to_string(),
+        ));
+    }
+    let contest = load_contest(conn, TARGET, cid)?;
+    // Submissions and their results are exported as the standings are
+    #[cfg(feature = "authorization")]
+    let privileged = user_claims.role >= Role::Author;
+    #[cfg(not(feature = "authorization"))]
+    let privileged = false;
+    check_ended(TARGET, cid, &contest, privileged)?;
+    let now = Utc::now();
+    let mut feed = EventFeed::default();
+
+    feed.create(
+        "contests",
+        json!({
+            "id": cid.to_string(),
+            "name": contest.name,
+            "formal_name": contest.name,
+            "start_time": abs_time(contest.from),
+            "duration": rel_time(contest.to - contest.from),
+            "scoreboard_freeze_duration": null,
+            "penalty_time": PENALTY_TIME,
+        }),
+    );
+    for (id, name, solved, penalty) in JUDGEMENT_TYPES {
+        feed.create(
+            "judgement-types",
+            json!({ "id": id, "name": name, "solved": solved, "penalty": penalty }),
+        );
+    }
+    for language in &config.languages {
+        feed.create(
+            "languages",
+            json!({ "id": language.name, "name": language.name }),
+        );
+    }
+    let problems: Vec<_> = contest
+        .problem_ids
+        .iter()
+        .filter_map(|pid| config.get_problem(*pid))
+        .collect();
+    for (ordinal, problem) in problems.iter().enumerate() {
+        let time_limit = max_limit(problem.cases.iter().map(|case| case.time_limit));
+        feed.create(
+            "problems",
+            json!({
+                "id": problem.id.to_string(),
+                "label": contest.label_of(problem.id),
+                "name": problem.name,
+                "ordinal": ordinal,
+                "time_limit": (time_limit != 0).then(|| time_limit as f64 / 1e6),
+                "test_data_count": problem.cases.len(),
+            }),
+        );
+    }
+
+    // Users are teams of their own, unless they are members of a team of the contest
+    let teams = teams::get_teams(conn, cid)?;
+    let members: HashSet<u32> = teams
+        .iter()
+        .flat_map(|team| team.member_ids.iter().copied())
+        .collect();
+    let mut participants = HashSet::new();
+    for user in models::get_some_users(
+        conn,
+        contest
+            .user_ids
+            .iter()
+            .filter(|uid| !members.contains(uid))
+            .map(|uid| *uid as i32)
+            .collect(),
+    )? {
+        let id = format!("u{}", user.id);
+        feed.create("teams", json!({ "id": id, "name": user.user_name }));
+        participants.insert(id);
+    }
+    for team in &teams {
+        let id = format!("t{}", team.id);
+        feed.create("teams", json!({ "id": id, "name": team.name }));
+        participants.insert(id);
+    }
+
+    // Submissions in personal windows are timed from the start of each window
+    let mut starts = HashMap::new();
+    if contest.duration.is_some() {
+        for participation in models::get_participations(conn, cid as i32)? {
+            let window = Participation::new(&participation, &contest);
+            if window.kind == SubmissionKind::Official {
+                starts.insert(window.user_id, window.from);
+            }
+        }
+    }
+
+    let jobs = models::get_jobs(
+        conn,
+        JobFilter {
+            contest_id: Some(cid as i32),
+            ..Default::default()
+        },
+    )?;
+    let mut judgements = vec![];
+    let mut pending = false;
+    let mut last_update = contest.to;
+    for job in jobs {
+        if job.submission_kind != SubmissionKind::Official || job.job_state == JobStatus::Canceled {
+            continue;
+        }
+        let team_id = match job.team_id {
+            Some(tid) => format!("t{tid}"),
+            None => format!("u{}", job.user_id),
+        };
+        if !participants.contains(&team_id) {
+            continue;
+        }
+        let start = match job.team_id {
+            Some(_) => contest.from,
+            None => *starts.get(&(job.user_id as u32)).unwrap_or(&contest.from),
+        };
+        let created_time = job.created_time.and_local_timezone(Utc).unwrap();
+        let updated_time = job.updated_time.and_local_timezone(Utc).unwrap();
+        feed.create(
+            "submissions",
+            json!({
+                "id": job.id.to_string(),
+                "language_id": job.lang,
+                "problem_id": job.problem_id.to_string(),
+                "team_id": team_id,
+                "time": abs_time(created_time),
+                "contest_time": rel_time(created_time - start),
+                "files": [],
+            }),
+        );
+        if job.job_state != JobStatus::Finished {
+            pending = true;
+            continue;
+        }
+        last_update = last_update.max(updated_time);
+        judgements.push(json!({
+            "id": job.id.to_string(),
+            "submission_id": job.id.to_string(),
+            "judgement_type_id": judgement_type(job.result),
+            "start_time": abs_time(created_time),
+            "start_contest_time": rel_time(created_time - start),
+            "end_time": abs_time(updated_time),
+            "end_contest_time": rel_time(updated_time - start),
+        }));
+    }
+    for judgement in judgements {
+        feed.create("judgements", judgement);
+    }
+
+    // Results are final once the contest ends and all submissions are judged
+    let ended = now >= contest.to;
+    let finalized = (ended && !pending).then(|| abs_time(last_update));
+    feed.create(
+        "state",
+        json!({
+            "started": (now >= contest.from).then(|| abs_time(contest.from)),
+            "ended": ended.then(|| abs_time(contest.to)),
+            "frozen": null,
+            "thawed": null,
+            "finalized": finalized,
+            "end_of_updates": finalized,
+        }),
+    );
+
+    let body: String = feed
+        .events
+        .iter()
+        .map(|event| event.to_string() + "\n")
+        .collect();
+
+    log::info!(target: TARGET, "Request done");
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .body(body))
+}
@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use actix_web::{
+    get, post,
+    web::{self, Data, Json, Path},
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{persistent::models, DbPool};
+
+#[cfg(feature = "authorization")]
+use crate::authorization::{Role, UserClaims};
+
+use super::{
+    contests::{load_contest, parse_ids},
+    err::{Error, Reason},
+};
+
+/// A team sharing its submissions and standing in a contest
+#[derive(Clone, Serialize)]
+pub struct Team {
+    pub id: u32,
+    pub contest_id: u32,
+    pub name: String,
+    pub member_ids: Vec<u32>,
+}
+
+impl From<models::Team> for Team {
+    fn from(team: models::Team) -> Self {
+        Self {
+            id: team.id as u32,
+            contest_id: team.contest_id as u32,
+            name: team.team_name,
+            member_ids: parse_ids(&team.member_ids),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TeamForm {
+    pub name: String,
+    pub member_ids: Vec<u32>,
+}
+
+/// Get all teams in a contest
+///
+/// Teams whose members have all left the contest are disbanded, and left out.
+pub fn get_teams(conn: &mut SqliteConnection, cid: u32) -> Result<Vec<Team>, Error> {
+    Ok(models::get_teams(conn, cid as i32)?
+        .into_iter()
+        .map(Team::from)
+        .filter(|team| !team.member_ids.is_empty())
+        .collect())
+}
+
+/// Get the team of a user in a contest, if any
+pub fn find_team(conn: &mut SqliteConnection, cid: u32, uid: u32) -> Result<Option<Team>, Error> {
+    Ok(get_teams(conn, cid)?
+        .into_iter()
+        .find(|team| team.member_ids.contains(&uid)))
+}
+
+#[post("/contests/{id}/teams")]
+pub async fn new_team(
+    id: Path<u32>,
+    form: Json<TeamForm>,
+    pool: Data<DbPool>,
+    #[cfg(feature = "authorization")] user_claims: UserClaims,
+) -> Result<Json<Team>, Error> {
+    const TARGET: &str = "POST /contests/{id}/teams";
+    log::info!(target: TARGET, "Request received");
+
+    // Users can only register teams they are in
+    #[cfg(feature = "authorization")]
+    if user_claims.role < Role::Author && !form.member_ids.contains(&user_claims.id) {
+        return Err(Error::new(
+            Reason::Forbidden,
+            "You are not allowed to register teams of others".to_string(),
+        ));
+    }
+
+    let cid = id.into_inner();
+    let TeamForm { name, member_ids } = form.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    let team = conn.immediate_transaction(|conn| {
+        let contest = load_contest(conn, TARGET, cid)?;
+
+        // Members would start their personal windows at different times
+        if contest.duration.is_some() {
+            log::info!(target: TARGET, "Contest {cid} has personal windows");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Contest {cid} has personal windows, so it can't have teams"),
+            ));
+        }
+        if member_ids.is_empty() {
+            log::info!(target: TARGET, "Team {name} has no members");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Team {name} has no members"),
+            ));
+        }
+        if member_ids.iter().collect::<HashSet<_>>().len() != member_ids.len() {
+            log::info!(target: TARGET, "Duplicate members in team {name}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Duplicate members in team {name}"),
+            ));
+        }
+        for uid in &member_ids {
+            if !contest.user_ids.contains(uid) {
+                log::info!(target: TARGET, "User {uid} not in contest {cid}");
+                return Err(Error::new(
+                    Reason::InvalidArgument,
+                    format!("User {uid} not in contest {cid}"),
+                ));
+            }
+        }
+
+        // Submissions made in the contest would change hands
+        if Utc::now() >= contest.from {
+            log::info!(target: TARGET, "Contest {cid} has already begun");
+            return Err(Error::new(
+                Reason::InvalidState,
+                format!("Contest {cid} has already begun"),
+            ));
+        }
+        let teams = get_teams(conn, cid)?;
+        if teams.iter().any(|team| team.name == name) {
+            log::info!(target: TARGET, "Team name '{name}' already exists in contest {cid}");
+            return Err(Error::new(
+                Reason::InvalidArgument,
+                format!("Team name '{name}' already exists in contest {cid}"),
+            ));
+        }
+        for team in &teams {
+            if let Some(uid) = member_ids.iter().find(|uid| team.member_ids.contains(uid)) {
+                log::info!(target: TARGET, "User {uid} already in team {}", team.id);
+                return Err(Error::new(
+                    Reason::InvalidState,
+                    format!("User {uid} already in team {}", team.id),
+                ));
+            }
+        }
+
+        let team = Team {
+            id: models::teams_count(conn)? as u32 + 1,
+            contest_id: cid,
+            name,
+            member_ids,
+        };
+        models::new_team(conn, team.clone().into())?;
+        Ok(team)
+    })?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(team))
+}
+
+#[get("/contests/{id}/teams")]
+pub async fn get_contest_teams(
+    id: Path<u32>,
+    pool: Data<DbPool>,
+) -> Result<Json<Vec<Team>>, Error> {
+    const TARGET: &str = "GET /contests/{id}/teams";
+    log::info!(target: TARGET, "Request received");
+
+    let cid = id.into_inner();
+    let conn = &mut web::block(move || pool.get()).await??;
+
+    load_contest(conn, TARGET, cid)?;
+    let teams = get_teams(conn, cid)?;
+
+    log::info!(target: TARGET, "Request done");
+    Ok(Json(teams))
+}
@@ -1,7 +1,12 @@
-use std::{fs::File, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    fs::File,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use clap::Parser;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 fn get_default_address() -> String {
@@ -12,6 +17,29 @@ fn get_default_port() -> u16 {
     12345
 }
 
+/// The wall clock time limit defaults to this multiple of the CPU time limit
+const WALL_TIME_FACTOR: u32 = 3;
+
+fn get_default_output_limit() -> u64 {
+    64 << 20
+}
+
+fn get_default_epsilon() -> f64 {
+    1e-6
+}
+
+fn get_default_cache_dir() -> PathBuf {
+    "cache".into()
+}
+
+fn get_default_cache_size() -> u64 {
+    1 << 30
+}
+
+fn get_default_case_slots() -> u32 {
+    1
+}
+
 /// Server config
 #[derive(Clone, Deserialize)]
 pub struct Server {
@@ -21,24 +49,147 @@ pub struct Server {
     pub bind_port: u16,
 }
 
+/// Judger config
+#[derive(Clone, Deserialize)]
+pub struct Judger {
+    /// Directory of the compile cache, shared between judger processes
+    #[serde(default = "get_default_cache_dir")]
+    pub cache_dir: PathBuf,
+    /// Size limit of the compile cache in bytes, 0 to disable the cache
+    #[serde(default = "get_default_cache_size")]
+    pub cache_size: u64,
+    /// Number of cases of a job judged at the same time
+    #[serde(default = "get_default_case_slots")]
+    pub case_slots: u32,
+}
+
+impl Default for Judger {
+    fn default() -> Self {
+        Self {
+            cache_dir: get_default_cache_dir(),
+            cache_size: get_default_cache_size(),
+            case_slots: get_default_case_slots(),
+        }
+    }
+}
+
 /// A test case of a problem
 #[derive(Clone, Deserialize)]
 pub struct Case {
     pub score: f64,
     pub input_file: PathBuf,
     pub answer_file: PathBuf,
+    /// CPU time limit in microseconds, 0 for unlimited
     pub time_limit: u32,
+    /// Wall clock time limit in microseconds, defaults to a multiple of the CPU time limit
+    #[serde(default)]
+    pub wall_time_limit: Option<u32>,
     pub memory_limit: u32,
+    /// Overrides the output limit of the problem
+    #[serde(default)]
+    pub output_limit: Option<u64>,
+    /// Attach a full diff to the info of a wrong answer, intended for sample cases
+    #[serde(default)]
+    pub show_diff: bool,
+    /// Whether the case is a sample, whose details are shown to everyone
+    #[serde(default)]
+    pub sample: bool,
+}
+
+impl Case {
+    /// Get the wall clock time limit of this case, `None` for unlimited
+    pub fn wall_time_limit(&self) -> Option<Duration> {
+        match self
+            .wall_time_limit
+            .unwrap_or_else(|| self.time_limit.saturating_mul(WALL_TIME_FACTOR))
+        {
+            0 => None,
+            limit => Some(Duration::from_micros(limit as u64)),
+        }
+    }
 }
 
 /// Problem type
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ProblemType {
     Standard,
     Strict,
     Spj,
     DynamicRanking,
+    /// Contestants submit the output of each case directly instead of source code
+    AnswerOnly,
+}
+
+/// How the score of a subtask is aggregated from its cases
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtaskScoring {
+    /// Scale the total score by the lowest fraction earned, skipping the rest after a failure
+    #[default]
+    Min,
+    /// Add up the fraction earned in each case
+    Sum,
+}
+
+/// How a special judge reports its verdict
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckerProtocol {
+    /// The verdict on the first line of stdout, followed by the info
+    #[default]
+    Default,
+    /// The verdict in the exit code of a testlib checker, with the message on stderr
+    Testlib,
+}
+
+/// A program compiled by the judger, e.g. a checker run as `checker input output answer`
+#[derive(Clone, Deserialize)]
+pub struct CheckerSource {
+    /// The language to compile the program with
+    pub language: String,
+    pub source: PathBuf,
+    /// Other files copied to the compile directory, e.g. `testlib.h`
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+/// Built-in checker comparing the output with the answer
+#[derive(Clone, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum Checker {
+    /// Compare line by line, ignoring trailing whitespace
+    Standard,
+    /// Compare byte by byte
+    Strict,
+    /// Compare whitespace-separated tokens
+    Token {
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    /// Compare whitespace-separated tokens as floating-point numbers
+    Float {
+        #[serde(default = "get_default_epsilon")]
+        absolute: f64,
+        #[serde(default = "get_default_epsilon")]
+        relative: f64,
+    },
+}
+
+/// Trusted files a submission is compiled with, for function-implementation problems
+#[derive(Clone, Deserialize)]
+pub struct Grader {
+    /// The language this grader is used for
+    pub language: String,
+    /// Source files substituted for `%GRADER%` in the compile command
+    #[serde(default)]
+    pub sources: Vec<PathBuf>,
+    /// Other files copied to the working directory, e.g. headers and stub libraries
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Extra compile arguments substituted for `%GRADER%` after the sources
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 /// A problem
@@ -49,21 +200,175 @@ pub struct Problem {
     #[serde(rename = "type")]
     pub typ: ProblemType,
     pub misc: Option<Value>,
+    /// Markdown file of the statement, served along with the sample cases
+    #[serde(default)]
+    pub statement: Option<PathBuf>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub difficulty: Option<u32>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Where the problem comes from, e.g. the contest it first appeared in
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub checker: Option<Checker>,
+    /// Output limit in bytes, 0 for unlimited
+    #[serde(default = "get_default_output_limit")]
+    pub output_limit: u64,
+    /// Copy the case input to this file in the working directory instead of feeding it to stdin
+    #[serde(default)]
+    pub input_file_name: Option<String>,
+    /// Read the output from this file in the working directory instead of from stdout
+    #[serde(default)]
+    pub output_file_name: Option<String>,
+    /// Graders for function-implementation problems, one for each supported language
+    #[serde(default)]
+    pub graders: Vec<Grader>,
+    /// How the special judge reports its verdict
+    #[serde(default)]
+    pub checker_protocol: CheckerProtocol,
+    /// Compile the special judge from source instead of running `misc.special_judge`
+    #[serde(default)]
+    pub checker_source: Option<CheckerSource>,
+    /// How subtasks given by `misc.packing` are scored
+    #[serde(default)]
+    pub subtask_scoring: SubtaskScoring,
+    /// Skip the remaining cases after the first case that is not accepted
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    /// Check the input of a hack, which is valid if the validator exits successfully
+    #[serde(default)]
+    pub validator: Option<CheckerSource>,
+    /// Solution whose output on the input of a hack is taken as the answer
+    #[serde(default)]
+    pub reference: Option<CheckerSource>,
     pub cases: Vec<Case>,
 }
 
+impl Problem {
+    /// Get the built-in checker of this problem, if it uses one
+    pub fn checker(&self) -> Option<Checker> {
+        match self.typ {
+            ProblemType::Standard => Some(self.checker.clone().unwrap_or(Checker::Standard)),
+            ProblemType::Strict => Some(Checker::Strict),
+            ProblemType::AnswerOnly => Some(self.checker.clone().unwrap_or(Checker::Standard)),
+            _ => None,
+        }
+    }
+
+    /// Get the special judge command of this problem, if it uses one
+    pub fn special_judge(&self) -> Option<Vec<String>> {
+        match self.typ {
+            ProblemType::Spj | ProblemType::AnswerOnly => {
+                serde_json::from_value(self.misc.as_ref()?.get("special_judge")?.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the subtasks of this problem as lists of case indices counting from 0
+    ///
+    /// Cases not packed into any subtask form subtasks on their own.
+    pub fn subtasks(&self) -> Vec<Vec<usize>> {
+        let packing: Vec<Vec<usize>> = self
+            .misc
+            .as_ref()
+            .and_then(|misc| misc.get("packing"))
+            .and_then(|packing| serde_json::from_value(packing.clone()).ok())
+            .unwrap_or_default();
+        let mut subtasks: Vec<Vec<usize>> = packing
+            .iter()
+            // Case 0 wraps around, so that it's rejected as an invalid case
+            .map(|subtask| subtask.iter().map(|id| id.wrapping_sub(1)).collect())
+            .collect();
+        for id in 0..self.cases.len() {
+            if !subtasks.iter().any(|subtask| subtask.contains(&id)) {
+                subtasks.push(vec![id]);
+            }
+        }
+        subtasks
+    }
+
+    /// Calculate the score from the fraction of the score earned in each case
+    pub fn score(&self, fractions: &[f64]) -> f64 {
+        self.subtasks()
+            .iter()
+            .map(|subtask| match self.subtask_scoring {
+                SubtaskScoring::Min => {
+                    let total: f64 = subtask.iter().map(|&id| self.cases[id].score).sum();
+                    let fraction = subtask.iter().map(|&id| fractions[id]).fold(1.0, f64::min);
+                    total * fraction
+                }
+                SubtaskScoring::Sum => subtask
+                    .iter()
+                    .map(|&id| self.cases[id].score * fractions[id])
+                    .sum(),
+            })
+            .sum()
+    }
+
+    /// The score of the problem when all cases are passed
+    pub fn full_score(&self) -> f64 {
+        self.cases.iter().map(|case| case.score).sum()
+    }
+
+    /// Whether submissions to this problem can be hacked
+    pub fn is_hackable(&self) -> bool {
+        self.validator.is_some()
+            && self.reference.is_some()
+            && !self.cases.is_empty()
+            && !self.is_answer_only()
+    }
+
+    /// Whether submissions to this problem are answers rather than source code
+    pub fn is_answer_only(&self) -> bool {
+        matches!(self.typ, ProblemType::AnswerOnly)
+    }
+
+    /// Get the grader for a specified language
+    pub fn get_grader(&self, lang: &str) -> Option<&Grader> {
+        self.graders.iter().find(|g| g.language == lang)
+    }
+
+    /// Whether submissions in a specified language are accepted
+    pub fn supports_lang(&self, lang: &str) -> bool {
+        self.graders.is_empty() || self.get_grader(lang).is_some()
+    }
+
+    /// Get the output limit of a case of this problem
+    pub fn output_limit(&self, case: &Case) -> u64 {
+        case.output_limit.unwrap_or(self.output_limit)
+    }
+}
+
+/// How to build a multi-file submission
+#[derive(Clone, Deserialize)]
+pub struct Build {
+    /// Command run in the source directory, with `%DIR%` and `%OUTPUT%` placeholders
+    pub command: Vec<String>,
+    /// Path of the executable relative to the source directory, if not placed at `%OUTPUT%`
+    #[serde(default)]
+    pub executable: Option<String>,
+}
+
 /// An available programming language
 #[derive(Clone, Deserialize)]
 pub struct Language {
     pub name: String,
     pub file_name: String,
     pub command: Vec<String>,
+    #[serde(default)]
+    pub build: Option<Build>,
 }
 
 /// Startup configuration
 #[derive(Clone, Deserialize)]
 pub struct Config {
     pub server: Server,
+    #[serde(default)]
+    pub judger: Judger,
     pub problems: Vec<Problem>,
     pub languages: Vec<Language>,
 }
@@ -105,5 +410,57 @@ fn parse_config(path: &str) -> Result<(String, Config), std::io::Error> {
     let path = PathBuf::from(path);
     let file = File::open(path)?;
     let config: Config = serde_json::from_reader(file)?;
+
+    for problem in &config.problems {
+        let invalid = |message: String| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{message} in problem {}", problem.id),
+            ))
+        };
+
+        if matches!(problem.typ, ProblemType::Spj)
+            && problem.special_judge().is_none()
+            && problem.checker_source.is_none()
+        {
+            return invalid("Missing special judge".to_string());
+        }
+        if let Some(source) = &problem.checker_source {
+            if config.get_lang(&source.language).is_none() {
+                return invalid(format!("Unknown checker language {}", source.language));
+            }
+        }
+        for source in [&problem.validator, &problem.reference]
+            .into_iter()
+            .flatten()
+        {
+            if config.get_lang(&source.language).is_none() {
+                return invalid(format!("Unknown language {}", source.language));
+            }
+        }
+
+        // Packed cases must exist, and belong to only one subtask
+        let mut packed = 0;
+        for subtask in problem.subtasks() {
+            if subtask.iter().any(|&id| id >= problem.cases.len()) {
+                return invalid("Invalid case in packing".to_string());
+            }
+            packed += subtask.len();
+        }
+        if packed != problem.cases.len() {
+            return invalid("Case packed more than once".to_string());
+        }
+
+        // File names for file-based I/O must not escape the working directory
+        for name in [&problem.input_file_name, &problem.output_file_name]
+            .into_iter()
+            .flatten()
+        {
+            if Path::new(name).file_name() != Some(OsStr::new(name)) {
+                return invalid(format!("Invalid file name {name}"));
+            }
+        }
+    }
+
     Ok((path_str, config))
 }
@@ -1,6 +1,12 @@
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{self, Read};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use amiquip::{
@@ -10,11 +16,48 @@ use chrono::Utc;
 use diesel::connection::SimpleConnection;
 use diesel::prelude::*;
 use temp_dir::TempDir;
-use wait_timeout::ChildExt;
 
-use crate::api::jobs::{CaseResult, Job, JobResult, JobStatus};
-use crate::config::{Config, ProblemType};
-use crate::persistent::models;
+use crate::api::hacks::HackState;
+use crate::api::jobs::{is_safe_path, CaseResult, Job, JobResult, JobStatus, Submission};
+use crate::config::{Case, Config, Problem, SubtaskScoring};
+use crate::persistent::models::{self, HackFilter};
+
+mod cache;
+mod checker;
+mod hack;
+mod sandbox;
+
+use cache::Cache;
+use checker::Checkers;
+
+/// A task sent to the judgers through the message queue
+#[derive(Clone, Copy, Debug)]
+pub enum Message {
+    Job(i32),
+    Hack(i32),
+}
+
+impl Message {
+    /// Encode the message as a tag byte followed by the id
+    pub fn to_bytes(self) -> [u8; 5] {
+        let (tag, id) = match self {
+            Message::Job(id) => (0, id),
+            Message::Hack(id) => (1, id),
+        };
+        let mut bytes = [tag; 5];
+        bytes[1..].copy_from_slice(&id.to_ne_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let id = i32::from_ne_bytes(bytes.get(1..)?.try_into().ok()?);
+        match bytes[0] {
+            0 => Some(Message::Job(id)),
+            1 => Some(Message::Hack(id)),
+            _ => None,
+        }
+    }
+}
 
 /// Auxiliary function for reading from a file
 fn read(mut f: File) -> Result<String, io::Error> {
@@ -23,23 +66,32 @@ fn read(mut f: File) -> Result<String, io::Error> {
     Ok(buf)
 }
 
-/// Auxiliary function for trimming whitespace
-fn trim(f: File) -> Result<String, io::Error> {
-    let buf = read(f)?;
-
-    // Trim whitespace at EOF
-    let buf = buf.trim_end();
-
-    // Trim whitespace at EOF
-    let mut result = String::new();
-    for line in buf.split('\n') {
-        result.push_str(line.trim_end());
+/// Save the files of a multi-file submission to a directory
+fn save_files(dir: &Path, files: &BTreeMap<String, String>) -> Result<(), io::Error> {
+    for (name, content) in files {
+        // Files must not escape the directory even if the database is tampered with
+        if !is_safe_path(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid file name: {name}"),
+            ));
+        }
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, content)?;
     }
-    Ok(result)
+    Ok(())
 }
 
 /// Judge given code and update the result in real time
-pub fn judge(conn: &mut SqliteConnection, config: &Config, name: &str, jid: i32) {
+pub fn judge(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    checkers: &Checkers,
+    cache: &Cache,
+    name: &str,
+    jid: i32,
+) {
     let target = &format!("{name}@job{jid}");
 
     let mut job: Job = models::get_job(conn, jid).unwrap().into();
@@ -50,13 +102,11 @@ pub fn judge(conn: &mut SqliteConnection, config: &Config, name: &str, jid: i32)
         return;
     }
 
-    let code = &job.submission.source_code;
-    let lang = config.get_lang(&job.submission.language).unwrap();
     let problem = config.get_problem(job.submission.problem_id).unwrap();
     log::info!(
         target: target,
         "New judge task started, lang: {}, problem id: {}",
-        lang.name,
+        job.submission.language,
         problem.id
     );
 
@@ -71,109 +121,445 @@ pub fn judge(conn: &mut SqliteConnection, config: &Config, name: &str, jid: i32)
         };
     }
 
+    // Auxiliary macro for aborting the job due to a system error
+    macro_rules! abort {
+        ($($x:tt)+) => {
+            log::error!(target: target, $($x)+);
+            job.state = JobStatus::Finished;
+            job.result = JobResult::SystemError;
+            push!();
+            return;
+        };
+    }
+
     // Create a temp directory for use
     let dir = TempDir::new().unwrap();
 
-    // Save code to a source file
-    let source = dir.child(&lang.file_name);
-    fs::write(source.clone(), code).unwrap();
-
-    // Executable file
-    let exec = dir.child("main");
+    // Answer-only submissions have nothing to compile
+    let exec = if job.submission.answers.is_some() {
+        job.state = JobStatus::Running;
+        job.cases[0].result = JobResult::Skipped;
+        push!();
+        None
+    } else {
+        // Compile
+        let now = Instant::now();
+        job.state = JobStatus::Running;
+        push!();
 
-    // Substitute %INPUT% and %OUTPUT% in args
-    let args: Vec<&str> = lang
-        .command
-        .iter()
-        .map(|arg| match arg.as_ref() {
-            "%INPUT%" => source.to_str().unwrap(),
-            "%OUTPUT%" => exec.to_str().unwrap(),
-            _ => arg,
-        })
-        .collect();
+        let (compiled, exec) =
+            match compile(config, cache, problem, &job.submission, dir.path(), target) {
+                Ok(compiled) => compiled,
+                Err(err) => {
+                    abort!("{err}");
+                }
+            };
 
-    // Compile
-    let now = Instant::now();
-    job.state = JobStatus::Running;
-    push!();
+        // Compilation error
+        if !compiled.success {
+            log::info!(target: target, "Compilation error");
+            job = Job {
+                state: JobStatus::Finished,
+                result: JobResult::CompilationError,
+                ..job
+            };
+            job.cases[0] = CaseResult {
+                id: 0,
+                result: JobResult::CompilationError,
+                time: now.elapsed().as_micros() as u32,
+                wall_time: now.elapsed().as_micros() as u32,
+                memory: 0,
+                info: compiled.info,
+            };
+            push!();
+            return;
+        }
 
-    let mut child = Command::new(args[0])
-        .args(args.iter().skip(1))
-        .stderr(Stdio::piped())
-        .spawn()
-        .expect("Unable to spawn child process");
-    let result = child.wait();
-
-    // Compilation error
-    if result.is_err() || !result.unwrap().success() {
-        log::info!(target: target, "Compilation error");
-        job = Job {
-            state: JobStatus::Finished,
-            result: JobResult::CompilationError,
-            ..job
-        };
+        // Compilation success
         job.cases[0] = CaseResult {
             id: 0,
-            result: JobResult::CompilationError,
+            result: JobResult::CompilationSuccess,
             time: now.elapsed().as_micros() as u32,
+            wall_time: now.elapsed().as_micros() as u32,
             memory: 0,
-            info: {
-                let mut buffer = String::new();
-                child
-                    .stderr
-                    .take()
-                    .expect("Unable to read stderr from compiler process")
-                    .read_to_string(&mut buffer)
-                    .expect("Unable to read stderr from compiler process");
-                buffer
-            },
+            info: compiled.info,
         };
         push!();
-        return;
-    }
-
-    // Compilation success
-    job.cases[0] = CaseResult {
-        id: 0,
-        result: JobResult::CompilationSuccess,
-        time: now.elapsed().as_micros() as u32,
-        memory: 0,
-        info: {
-            let mut buffer = String::new();
-            child
-                .stderr
-                .take()
-                .expect("Unable to read stderr from compiler process")
-                .read_to_string(&mut buffer)
-                .expect("Unable to read stderr from compiler process");
-            buffer
-        },
+        Some(exec)
     };
-    push!();
 
     // Intermediate job result
     let mut job_result = JobResult::Accepted;
 
+    // Fraction of the score earned in each case
+    let mut fractions = vec![0.0; problem.cases.len()];
+
+    // Stop at the first failure if either the problem or the contest asks to
+    let cid = job.submission.contest_id;
+    let stop_on_failure = problem.stop_on_failure
+        || match cid {
+            0 => false,
+            _ => match models::get_contest(conn, cid as i32) {
+                Ok(contest) => contest.stop_on_failure,
+                Err(err) => {
+                    abort!("Unable to get contest {cid}: {err}");
+                }
+            },
+        };
+    // Index of the first failed case known so far, after which cases need not be judged
+    let first_failure = AtomicUsize::new(usize::MAX);
+
+    // Compile the checker before the first case, if the problem has one
+    let special_judge = checkers.command(problem, config);
+
+    // Cases in a subtask scored by the minimum are judged in order, since a failure skips the
+    // rest of them, while other cases are independent of each other
+    let mut chains = match problem.subtask_scoring {
+        SubtaskScoring::Min => problem.subtasks(),
+        SubtaskScoring::Sum => (0..problem.cases.len()).map(|id| vec![id]).collect(),
+    };
+    for chain in &mut chains {
+        chain.sort();
+    }
+    chains.sort();
+
+    let answers = job.submission.answers.clone();
+    let task = Task {
+        target,
+        problem,
+        dir: dir.path(),
+        exec: exec.as_deref(),
+        answers: answers.as_deref(),
+        special_judge: &special_judge,
+    };
+    let slots = (config.judger.case_slots as usize).clamp(1, chains.len().max(1));
+    let chains = Mutex::new(chains.into_iter());
+    let (sender, receiver) = mpsc::channel();
+
     // Judge
-    for (id, case) in problem.cases.iter().enumerate() {
-        let id = id as u32 + 1;
-        let case_result = &mut job.cases[id as usize];
-
-        // Auxiliary macro for reporting an system error
-        macro_rules! system_error {
-            ($($x:tt)+) => {
-                log::error!(target: target, $($x)+);
-                if job_result == JobResult::Accepted {
-                    job_result = JobResult::SystemError;
+    thread::scope(|scope| {
+        for _ in 0..slots {
+            let sender = sender.clone();
+            let (task, chains, first_failure) = (&task, &chains, &first_failure);
+            scope.spawn(move || loop {
+                let chain = chains.lock().unwrap().next();
+                let Some(chain) = chain else {
+                    break;
+                };
+                let mut failed = false;
+                for index in chain {
+                    let outcome = if failed || index > first_failure.load(Ordering::Relaxed) {
+                        log::info!(target: target, "Test case {}: Skipped", index + 1);
+                        (CaseResult::new(index as u32 + 1, JobResult::Skipped), 0.0)
+                    } else {
+                        judge_case(task, index)
+                    };
+                    failed |= outcome.1 == 0.0;
+                    if stop_on_failure && outcome.0.result != JobResult::Accepted {
+                        first_failure.fetch_min(index, Ordering::Relaxed);
+                    }
+                    sender.send((index, outcome)).unwrap();
+                }
+            });
+        }
+        drop(sender);
+
+        // Apply the results in the order of cases, so that updates are deterministic
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (index, outcome) in receiver {
+            pending.insert(index, outcome);
+            while let Some((case_result, fraction)) = pending.remove(&next) {
+                // Record first non-accepted result
+                if case_result.result != JobResult::Accepted && job_result == JobResult::Accepted {
+                    job_result = case_result.result;
+                }
+                fractions[next] = fraction;
+                job.score = problem.score(&fractions);
+                job.cases[next + 1] = case_result;
+                next += 1;
+
+                // Give the verdict right away, ignoring cases still being judged
+                if stop_on_failure && job_result != JobResult::Accepted {
+                    for case in &mut job.cases[next + 1..] {
+                        log::info!(target: target, "Test case {}: Skipped", case.id);
+                        case.result = JobResult::Skipped;
+                    }
+                    job.state = JobStatus::Finished;
+                    job.result = job_result;
+                    push!();
+                    next = usize::MAX;
+                    break;
                 }
-                case_result.result = JobResult::SystemError;
                 push!();
-                continue;
+            }
+        }
+    });
+
+    if job.state != JobStatus::Finished {
+        // Inputs of successful hacks are extra cases, so that a rejudge does not undo the hacks
+        let hacks = models::get_hacks(
+            conn,
+            HackFilter {
+                job_id: Some(jid),
+                state: Some(HackState::Successful),
+                ..Default::default()
+            },
+        );
+        let hacks = match hacks {
+            Ok(hacks) => hacks,
+            Err(err) => {
+                abort!("Unable to get hacks of job {jid}: {err}");
+            }
+        };
+        job.cases.truncate(problem.cases.len() + 1);
+        for hack in hacks {
+            let id = job.cases.len() as u32;
+            let case_result = if job_result == JobResult::Accepted {
+                hack::judge_hack(&task, config, checkers, &hack, id)
+            } else {
+                log::info!(target: target, "Test case {id}: Skipped");
+                CaseResult::new(id, JobResult::Skipped)
             };
+            if case_result.result != JobResult::Accepted && job_result == JobResult::Accepted {
+                job_result = case_result.result;
+                job.score = 0.0;
+            }
+            job.cases.push(case_result);
+            push!();
         }
 
-        let input = File::open(case.input_file.clone());
-        let output = File::create(dir.child(".output"));
+        job.state = JobStatus::Finished;
+        job.result = job_result;
+        push!();
+    }
+
+    log::info!(target: target, "Judging ended");
+}
+
+/// Compile a submission in a directory, reusing an earlier compilation of the same sources
+///
+/// Returns the compile result and the path of the executable.
+fn compile(
+    config: &Config,
+    cache: &Cache,
+    problem: &Problem,
+    submission: &Submission,
+    dir: &Path,
+    target: &str,
+) -> Result<(cache::Compiled, PathBuf), String> {
+    let lang = config.get_lang(&submission.language).unwrap();
+
+    // Multi-file submissions are saved to a separate directory
+    let src_dir = match submission.files {
+        Some(_) => dir.join("src"),
+        None => dir.to_path_buf(),
+    };
+    let source = src_dir.join(&lang.file_name);
+
+    // Save code to source files
+    if let Some(files) = &submission.files {
+        save_files(&src_dir, files).map_err(|err| format!("Unable to save source files: {err}"))?;
+    } else {
+        fs::write(&source, &submission.source_code)
+            .map_err(|err| format!("Unable to save source file: {err}"))?;
+    }
+
+    // Executable file
+    let mut exec = dir.join("main");
+
+    // The compile result only depends on the language, the sources and the grader
+    let mut key = cache::Key::new();
+    key.add(&lang.name);
+    match &submission.files {
+        Some(files) => {
+            key.add(files.len().to_le_bytes());
+            for (name, content) in files {
+                key.add(name).add(content);
+            }
+        }
+        None => {
+            key.add(&submission.source_code);
+        }
+    }
+
+    // Copy trusted grader files to the source directory
+    let grader = problem.get_grader(&lang.name);
+    let mut grader_args = vec![];
+    if let Some(grader) = grader {
+        key.add(grader.sources.len().to_le_bytes());
+        for file in grader.sources.iter().chain(&grader.files) {
+            let path = file.file_name().map(|name| src_dir.join(name));
+            match path.map(|path| fs::copy(file, &path).map(|_| path)) {
+                Some(Ok(path)) => {
+                    let content = fs::read(&path)
+                        .map_err(|err| format!("Unable to read grader file: {err}"))?;
+                    key.add(path.file_name().unwrap().to_str().unwrap())
+                        .add(content);
+                    if grader.sources.contains(file) {
+                        grader_args.push(path.to_str().unwrap().to_string());
+                    }
+                }
+                _ => {
+                    return Err(format!("Unable to copy grader file: {}", file.display()));
+                }
+            }
+        }
+        grader_args.extend(grader.args.iter().cloned());
+    }
+
+    // Multi-file submissions are built by the build command if there is one
+    let build = match submission.files {
+        Some(_) => lang.build.as_ref(),
+        None => None,
+    };
+    let command = build.map_or(&lang.command, |build| &build.command);
+
+    // Substitute %INPUT%, %OUTPUT%, %DIR% and %GRADER% in args
+    let mut args: Vec<String> = command
+        .iter()
+        .flat_map(|arg| match arg.as_ref() {
+            "%INPUT%" => vec![source.to_str().unwrap().to_string()],
+            "%OUTPUT%" => vec![exec.to_str().unwrap().to_string()],
+            "%DIR%" => vec![src_dir.to_str().unwrap().to_string()],
+            "%GRADER%" => grader_args.clone(),
+            _ => vec![arg.clone()],
+        })
+        .collect();
+    // Put the grader at the end if the command has no place for it
+    if !command.iter().any(|arg| arg == "%GRADER%") {
+        args.extend(grader_args);
+    }
+
+    key.add(command.len().to_le_bytes());
+    for arg in command {
+        key.add(arg);
+    }
+    if let Some(grader) = grader {
+        for arg in &grader.args {
+            key.add(arg);
+        }
+    }
+    let executable = build.and_then(|build| build.executable.as_ref());
+    key.add(executable.map_or("", |executable| executable));
+
+    // The build command may put the executable somewhere else
+    if let Some(executable) = executable {
+        exec = src_dir.join(executable);
+    }
+
+    // Reuse the result of an earlier compilation of the same sources
+    if let Some(compiled) = cache.get(&key, &exec) {
+        log::info!(target: target, "Compile result found in cache");
+        return Ok((compiled, exec));
+    }
+
+    let output = Command::new(&args[0])
+        .args(args.iter().skip(1))
+        .current_dir(&src_dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("Unable to spawn compiler: {err}"))?;
+    let compiled = cache::Compiled {
+        success: output.status.success(),
+        info: String::from_utf8_lossy(&output.stderr).into_owned(),
+    };
+
+    // A compiler killed by a signal may well succeed next time
+    if output.status.code().is_some() {
+        cache.put(&key, &compiled, &exec);
+    }
+    Ok((compiled, exec))
+}
+
+/// Everything needed to judge the cases of a job
+struct Task<'a> {
+    target: &'a str,
+    problem: &'a Problem,
+    dir: &'a Path,
+    /// The executable, unless the submission is answer-only
+    exec: Option<&'a Path>,
+    /// The answers of an answer-only submission
+    answers: Option<&'a [String]>,
+    special_judge: &'a Option<Result<Vec<String>, String>>,
+}
+
+/// Judge a case in its own working directory
+///
+/// Returns the result of the case and the fraction of the score earned.
+fn judge_case(task: &Task, index: usize) -> (CaseResult, f64) {
+    let id = index as u32 + 1;
+    let (target, problem) = (task.target, task.problem);
+    let case = &problem.cases[index];
+    let mut case_result = CaseResult::new(id, JobResult::Waiting);
+
+    // Each case has its own working directory, so that cases can be judged at the same time
+    let dir = task.dir.join(format!("case{id}"));
+    if let Err(err) = fs::create_dir(&dir) {
+        log::error!(target: target, "Unable to create working directory: {}", err);
+        case_result.result = JobResult::SystemError;
+        return (case_result, 0.0);
+    }
+    let outcome = run_case(task, case, &dir, case_result);
+    let _ = fs::remove_dir_all(&dir);
+    outcome
+}
+
+/// Run the program on a case and check its output
+fn run_case(
+    task: &Task,
+    case: &Case,
+    dir: &Path,
+    mut case_result: CaseResult,
+) -> (CaseResult, f64) {
+    let id = case_result.id;
+    let (target, problem) = (task.target, task.problem);
+
+    // Auxiliary macro for reporting an system error
+    macro_rules! system_error {
+        ($($x:tt)+) => {
+            log::error!(target: target, $($x)+);
+            case_result.result = JobResult::SystemError;
+            return (case_result, 0.0);
+        };
+    }
+
+    // Auxiliary macro for finishing the case with the fraction of the score earned
+    macro_rules! finish {
+        ($result: expr, $fraction: expr, $($x:tt)+) => {
+            log::info!(target: target, $($x)+);
+            case_result.result = $result;
+            return (case_result, $fraction);
+        };
+    }
+
+    // Auxiliary macro for updating results
+    macro_rules! update_result {
+        ($result: expr, $($x:tt)+) => {
+            finish!($result, 0.0, $($x)+);
+        };
+    }
+
+    let output_path = dir.join(problem.output_file_name.as_deref().unwrap_or(".output"));
+    let output_limit = problem.output_limit(case);
+
+    if let Some(answers) = task.answers {
+        // The answer is checked as if it were the output of the program
+        if let Err(err) = fs::write(&output_path, &answers[id as usize - 1]) {
+            system_error!("Unable to save answer: {}", err);
+        }
+    } else {
+        let exec = task.exec.unwrap();
+        let input = match &problem.input_file_name {
+            // Copy the input to the working directory for file-based I/O
+            Some(name) => fs::copy(&case.input_file, dir.join(name)).map(|_| Stdio::null()),
+            None => File::open(&case.input_file).map(Stdio::from),
+        };
+        let output = match &problem.output_file_name {
+            // The program creates the output file itself
+            Some(_) => Ok(Stdio::null()),
+            None => File::create(&output_path).map(Stdio::from),
+        };
 
         // Unable to open file
         if input.is_err() {
@@ -184,10 +570,16 @@ pub fn judge(conn: &mut SqliteConnection, config: &Config, name: &str, jid: i32)
         }
 
         // Child process
-        let child = Command::new(exec.clone())
+        let mut command = Command::new(exec);
+        command
+            .current_dir(dir)
             .stdin(input.unwrap())
-            .stdout(output.unwrap())
-            .spawn();
+            .stdout(output.unwrap());
+        sandbox::limit_output(&mut command, output_limit);
+        if case.time_limit != 0 {
+            sandbox::limit_cpu_time(&mut command, Duration::from_micros(case.time_limit as u64));
+        }
+        let child = command.spawn();
 
         // Unable to spawn process
         if child.is_err() {
@@ -196,122 +588,139 @@ pub fn judge(conn: &mut SqliteConnection, config: &Config, name: &str, jid: i32)
 
         let mut child = child.unwrap();
 
-        let now = Instant::now();
-
-        // Auxiliary macro for updating results
-        macro_rules! update_result {
-            ($result: expr, $($x:tt)+) => {
-                log::info!(target: target, $($x)+);
-
-                // Record first non-accepted result
-                if $result != JobResult::Accepted && job_result == JobResult::Accepted {
-                    job_result = $result;
-                }
-                case_result.result = $result;
-                case_result.time = now.elapsed().as_micros() as u32;
-
-                push!();
-                continue;
-            };
-        }
-
-        // Wait for the process to finish and check status code
-        match child.wait_timeout(if case.time_limit != 0 {
-            Duration::from_micros(case.time_limit as u64) + Duration::from_millis(500)
-        } else {
-            Duration::MAX
-        }) {
-            Ok(Some(status)) => {
-                // Exited, but with an error
-                if !status.success() {
-                    update_result!(JobResult::RuntimeError, "Test case {id}: Runtime error");
-                }
-            }
-            // Child hasn't exited yet
-            Ok(None) => {
-                match child.kill() {
-                    Ok(_) => {
-                        update_result!(
-                            JobResult::TimeLimitExceeded,
-                            "Test case {id}: Time limit exceeded"
-                        );
-                    }
-                    Err(err) => {
-                        system_error!("Unable to kill child process: {}", err);
-                    }
-                };
-            }
-            // Unknown error
+        // Wait for the process to finish and collect its resource usage
+        let usage = match sandbox::wait(&mut child, case.wall_time_limit()) {
+            Ok(usage) => usage,
             Err(err) => {
                 let _ = child.kill();
                 system_error!("Unknown error when executing program: {}", err);
             }
         };
-
-        // Check if time limit exceeded
-        if case.time_limit != 0 && now.elapsed().as_micros() as u32 > case.time_limit {
+        case_result.time = usage.cpu_time.as_micros() as u32;
+        case_result.wall_time = usage.wall_time.as_micros() as u32;
+        case_result.memory = usage.memory as u32;
+
+        // Check if time limit exceeded, either in CPU time or in wall clock time
+        if usage.killed
+            || (case.time_limit != 0 && case_result.time > case.time_limit)
+            || usage.status.signal() == Some(libc::SIGXCPU)
+        {
             update_result!(
                 JobResult::TimeLimitExceeded,
                 "Test case {id}: Time limit exceeded"
             );
         }
 
-        // Open the output file again
-        let output = File::open(dir.child(".output"));
-        if output.is_err() {
-            system_error!("Unable to open output file: {}", output.unwrap_err());
+        // Killed for writing too much
+        if usage.status.signal() == Some(libc::SIGXFSZ) {
+            update_result!(
+                JobResult::OutputLimitExceeded,
+                "Test case {id}: Output limit exceeded"
+            );
         }
-        let output = output.unwrap();
 
-        // Open the answer file
-        let answer = File::open(case.answer_file.clone());
-        if answer.is_err() {
-            system_error!("Unable to open answer file: {}", answer.unwrap_err());
+        // Exited, but with an error
+        if !usage.status.success() {
+            update_result!(JobResult::RuntimeError, "Test case {id}: Runtime error");
         }
-        let answer = answer.unwrap();
+    }
 
-        // Now we are sure that the process exited successfully
-        // Check the output
-        let (output, answer) = match problem.typ {
-            ProblemType::Standard => (trim(output), trim(answer)),
-            ProblemType::Strict => (read(output), read(answer)),
-            _ => {
-                system_error!("Unimplemented problem type");
+    // Open the output file again
+    let output = File::open(&output_path);
+    if let (Err(err), Some(name)) = (&output, &problem.output_file_name) {
+        if err.kind() == io::ErrorKind::NotFound {
+            case_result.info = format!("Output file {name} not found");
+            update_result!(JobResult::WrongAnswer, "Test case {id}: Wrong Answer");
+        }
+    }
+    if output.is_err() {
+        system_error!("Unable to open output file: {}", output.unwrap_err());
+    }
+    let output = output.unwrap();
+
+    // The child may ignore SIGXFSZ and exit normally with a truncated output
+    match output.metadata() {
+        Ok(metadata) => {
+            if output_limit != 0 && metadata.len() > output_limit {
+                update_result!(
+                    JobResult::OutputLimitExceeded,
+                    "Test case {id}: Output limit exceeded"
+                );
             }
-        };
+        }
+        Err(err) => {
+            system_error!("Unable to read output file metadata: {}", err);
+        }
+    }
 
-        if output.is_err() {
-            system_error!("Unable to read from output file: {}", output.unwrap_err());
+    // Now we are sure that the process exited successfully
+    // Check the output with the special judge if there is one
+    if let Some(command) = task.special_judge {
+        let verdict = command.clone().and_then(|command| {
+            checker::special_judge(&command, problem.checker_protocol, case, &output_path, dir)
+        });
+        match verdict {
+            Ok(verdict) => {
+                case_result.info = verdict.info;
+                finish!(
+                    verdict.result,
+                    verdict.fraction,
+                    "Test case {id}: {:?} ({})",
+                    verdict.result,
+                    verdict.fraction
+                );
+            }
+            Err(info) => {
+                case_result.info = info;
+                update_result!(
+                    JobResult::SpjError,
+                    "Test case {id}: SPJ error: {}",
+                    case_result.info
+                );
+            }
         }
-        if answer.is_err() {
-            system_error!("Unable to read from answer file: {}", answer.unwrap_err());
+    }
+
+    // Open the answer file
+    let answer = File::open(case.answer_file.clone());
+    if answer.is_err() {
+        system_error!("Unable to open answer file: {}", answer.unwrap_err());
+    }
+    let answer = answer.unwrap();
+
+    // Check the output with the built-in checker
+    let checker = match problem.checker() {
+        Some(checker) => checker,
+        None => {
+            system_error!("Unimplemented problem type");
         }
+    };
+    let (output, answer) = (read(output), read(answer));
+
+    if output.is_err() {
+        system_error!("Unable to read from output file: {}", output.unwrap_err());
+    }
+    if answer.is_err() {
+        system_error!("Unable to read from answer file: {}", answer.unwrap_err());
+    }
 
-        let (output, answer) = (output.unwrap(), answer.unwrap());
+    let (output, answer) = (output.unwrap(), answer.unwrap());
 
-        if output == answer {
-            job.score += case.score;
-            update_result!(JobResult::Accepted, "Test case {id}: Accepted");
-        } else {
+    match checker::check(&checker, &output, &answer) {
+        Ok(()) => {
+            finish!(JobResult::Accepted, 1.0, "Test case {id}: Accepted");
+        }
+        Err(info) => {
             log::info!(target: target, "Output: {output}*EOF*");
             log::info!(target: target, "Answer: {answer}*EOF*");
-            // The position where the output and the answer differ
-            let pos = output
-                .chars()
-                .zip(answer.chars())
-                .position(|(a, b)| a != b)
-                .unwrap_or(output.len());
-            case_result.info =
-                format!("Output differs from answer at the {pos}-th character (counting from 0)",);
+            case_result.info = if case.show_diff {
+                format!("{info}\n{}", checker::diff(&output, &answer))
+            } else {
+                info
+            };
             update_result!(JobResult::WrongAnswer, "Test case {id}: Wrong Answer");
         }
     }
-
-    job.state = JobStatus::Finished;
-    job.result = job_result;
-    push!();
-
-    log::info!(target: target, "Judging ended");
 }
 
 pub fn main(parent: u32, id: i32, config: Config) {
@@ -344,13 +753,23 @@ pub fn main(parent: u32, id: i32, config: Config) {
     let name = format!("judger{id}");
     log::info!(target: &name, "Judger process started");
 
+    let checkers = Checkers::new().expect("Unable to create checker directory");
+    let cache = Cache::new(&config.judger);
+
     for message in consumer.receiver() {
         match message {
             ConsumerMessage::Delivery(delivery) => {
-                let mut bytes = [0; 4];
-                bytes.clone_from_slice(&delivery.body);
-                let jid = i32::from_ne_bytes(bytes);
-                judge(sql_connection, &config, &name, jid);
+                match Message::from_bytes(&delivery.body) {
+                    Some(Message::Job(jid)) => {
+                        judge(sql_connection, &config, &checkers, &cache, &name, jid);
+                    }
+                    Some(Message::Hack(hid)) => {
+                        hack::hack(sql_connection, &config, &checkers, &cache, &name, hid);
+                    }
+                    None => {
+                        log::error!(target: &name, "Malformed message: {:?}", delivery.body);
+                    }
+                }
 
                 consumer
                     .ack(delivery)
@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Judger;
+
+/// File in a cache entry holding the compile result, whose mtime is the last use of the entry
+const RESULT_FILE: &str = "result.json";
+
+/// File in a cache entry holding the executable
+const EXECUTABLE_FILE: &str = "executable";
+
+/// Prefix of entries being written, which are invisible to lookups
+const TEMP_PREFIX: &str = ".tmp";
+
+/// Result of a compilation
+#[derive(Serialize, Deserialize)]
+pub struct Compiled {
+    pub success: bool,
+    /// Output of the compiler
+    pub info: String,
+}
+
+/// Key of a compilation, built from everything that affects its result
+pub struct Key(Sha256);
+
+impl Key {
+    pub fn new() -> Self {
+        Self(Sha256::new())
+    }
+
+    /// Add a piece of data to the key
+    pub fn add(&mut self, data: impl AsRef<[u8]>) -> &mut Self {
+        let data = data.as_ref();
+        // Prefix the length so that different splits of the same bytes give different keys
+        self.0.update((data.len() as u64).to_le_bytes());
+        self.0.update(data);
+        self
+    }
+
+    /// Name of the cache entry
+    fn name(&self) -> String {
+        self.0
+            .clone()
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// A content-addressed cache of compiled executables on disk, evicted in LRU order
+pub struct Cache {
+    dir: PathBuf,
+    /// Size limit in bytes, 0 if the cache is disabled
+    size: u64,
+    /// Counter for unique names of entries being written
+    counter: AtomicU32,
+}
+
+impl Cache {
+    pub fn new(config: &Judger) -> Self {
+        let mut size = config.cache_size;
+        if size != 0 {
+            if let Err(err) = fs::create_dir_all(&config.cache_dir) {
+                log::warn!(target: "cache", "Compile cache disabled: {err}");
+                size = 0;
+            }
+        }
+        Self {
+            dir: config.cache_dir.clone(),
+            size,
+            counter: AtomicU32::new(0),
+        }
+    }
+
+    /// Look up a compilation, copying the executable to `exec` if it succeeded
+    pub fn get(&self, key: &Key, exec: &Path) -> Option<Compiled> {
+        if self.size == 0 {
+            return None;
+        }
+        let entry = self.dir.join(key.name());
+        let result = fs::read(entry.join(RESULT_FILE)).ok()?;
+        let compiled: Compiled = serde_json::from_slice(&result).ok()?;
+        // The entry may be evicted by another judger meanwhile, which is just a miss
+        // Builds may put the executable in a directory they create, e.g. `target/release/main`
+        if compiled.success
+            && exec
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(entry.join(EXECUTABLE_FILE), exec))
+                .is_err()
+        {
+            return None;
+        }
+        // Mark the entry as recently used
+        let _ = File::options()
+            .write(true)
+            .open(entry.join(RESULT_FILE))
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        Some(compiled)
+    }
+
+    /// Save a compilation, with the executable at `exec` if it succeeded
+    pub fn put(&self, key: &Key, compiled: &Compiled, exec: &Path) {
+        if self.size == 0 {
+            return;
+        }
+        if let Err(err) = self.insert(key, compiled, exec) {
+            log::warn!(target: "cache", "Unable to save compile result: {err}");
+        }
+        if let Err(err) = self.evict() {
+            log::warn!(target: "cache", "Unable to evict compile cache: {err}");
+        }
+    }
+
+    fn insert(&self, key: &Key, compiled: &Compiled, exec: &Path) -> Result<(), io::Error> {
+        let entry = self.dir.join(key.name());
+        // Write to a temporary entry first, so that other judgers never see a partial one
+        let temp = self.dir.join(format!(
+            "{TEMP_PREFIX}-{}-{}",
+            process::id(),
+            self.counter.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&temp)?;
+        let result = (|| {
+            if compiled.success {
+                fs::copy(exec, temp.join(EXECUTABLE_FILE))?;
+            }
+            fs::write(temp.join(RESULT_FILE), serde_json::to_vec(compiled)?)?;
+            fs::rename(&temp, &entry)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&temp);
+        }
+        match result {
+            // Another judger may have saved the same compilation first, which is fine
+            Err(_) if entry.exists() => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Get the last use and the size of an entry
+    fn usage(entry: &Path) -> Result<(SystemTime, u64), io::Error> {
+        let mut size = 0;
+        for file in fs::read_dir(entry)? {
+            size += file?.metadata()?.len();
+        }
+        let used = fs::metadata(entry.join(RESULT_FILE))?.modified()?;
+        Ok((used, size))
+    }
+
+    /// Remove the least recently used entries until the cache fits in its size limit
+    fn evict(&self) -> Result<(), io::Error> {
+        let mut entries = vec![];
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_none_or(|name| name.starts_with(TEMP_PREFIX))
+            {
+                continue;
+            }
+            // Ignore entries removed by another judger meanwhile
+            if let Ok((used, size)) = Self::usage(&path) {
+                total += size;
+                entries.push((used, size, path));
+            }
+        }
+
+        entries.sort();
+        for (_, size, path) in entries {
+            if total <= self.size {
+                break;
+            }
+            let _ = fs::remove_dir_all(path);
+            total -= size;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use similar::TextDiff;
+use temp_dir::TempDir;
+
+use super::sandbox;
+use crate::api::jobs::JobResult;
+use crate::config::{Case, Checker, CheckerProtocol, CheckerSource, Config, Problem, ProblemType};
+
+/// Longest token shown in the info of a wrong answer
+const MAX_TOKEN_LEN: usize = 32;
+
+/// Characters shown on each side of the first difference in a line
+const EXCERPT_RADIUS: usize = 16;
+
+/// Longest unified diff attached to the info of a wrong answer
+const MAX_DIFF_LEN: usize = 2048;
+
+/// Time spent looking for the smallest diff, after which a coarser one is taken
+const DIFF_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Wall clock time limit of a special judge
+pub const SPJ_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// Exit codes of a testlib checker
+const TESTLIB_OK: i32 = 0;
+const TESTLIB_WA: i32 = 1;
+const TESTLIB_PE: i32 = 2;
+const TESTLIB_FAIL: i32 = 3;
+const TESTLIB_POINTS: i32 = 7;
+
+/// `_pc(k)` exits with this plus `k` when testlib is compiled with `-DPC_BASE_EXIT_CODE=16`
+const TESTLIB_PC_BASE: i32 = 16;
+
+/// The verdict of a special judge on a case
+pub struct Verdict {
+    pub result: JobResult,
+    /// Fraction of the score earned
+    pub fraction: f64,
+    pub info: String,
+}
+
+impl Verdict {
+    /// Make a verdict from the fraction of the score earned
+    fn new(fraction: f64, info: String) -> Self {
+        let result = if fraction == 1.0 {
+            JobResult::Accepted
+        } else if fraction == 0.0 {
+            JobResult::WrongAnswer
+        } else {
+            JobResult::PartiallyCorrect
+        };
+        Self {
+            result,
+            fraction,
+            info,
+        }
+    }
+}
+
+/// Auxiliary function for trimming whitespace
+fn trim(buf: &str) -> Vec<&str> {
+    // Trim whitespace at EOF, then at the end of each line
+    buf.trim_end()
+        .split('\n')
+        .map(|line| line.trim_end())
+        .collect()
+}
+
+/// Shorten a token so that the info stays readable
+fn shorten(token: &str) -> String {
+    if token.chars().count() > MAX_TOKEN_LEN {
+        token.chars().take(MAX_TOKEN_LEN).collect::<String>() + "..."
+    } else {
+        token.to_string()
+    }
+}
+
+/// Take the part of a line around given column (counting from 0)
+fn excerpt(line: &str, col: usize) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let from = col.saturating_sub(EXCERPT_RADIUS);
+    let to = (col + EXCERPT_RADIUS).min(chars.len());
+    let mut result = String::new();
+    if from > 0 {
+        result.push_str("...");
+    }
+    result.extend(&chars[from..to]);
+    if to < chars.len() {
+        result.push_str("...");
+    }
+    // Escape the excerpt so that whitespace differences are visible
+    format!("{result:?}")
+}
+
+/// Compare two lists of lines, reporting the line and column where they first differ
+fn compare(output: &[&str], answer: &[&str]) -> Result<(), String> {
+    for id in 0..output.len().max(answer.len()) {
+        let line = id + 1;
+        match (answer.get(id), output.get(id)) {
+            (Some(a), Some(o)) => {
+                if a == o {
+                    continue;
+                }
+                let col = o
+                    .chars()
+                    .zip(a.chars())
+                    .position(|(a, b)| a != b)
+                    .unwrap_or_else(|| o.chars().count().min(a.chars().count()));
+                return Err(format!(
+                    "line {line}, column {}: expected {}, found {}",
+                    col + 1,
+                    excerpt(a, col),
+                    excerpt(o, col)
+                ));
+            }
+            (Some(a), None) => {
+                return Err(format!(
+                    "line {line}: expected {}, found end of file",
+                    excerpt(a, 0)
+                ));
+            }
+            (None, Some(o)) => {
+                return Err(format!(
+                    "line {line}: expected end of file, found {}",
+                    excerpt(o, 0)
+                ));
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+/// Compare the tokens of the output and the answer one by one
+fn compare_tokens(
+    output: &str,
+    answer: &str,
+    eq: impl Fn(&str, &str) -> bool,
+) -> Result<(), String> {
+    let mut output = output.split_whitespace();
+    let mut answer = answer.split_whitespace();
+    for id in 1.. {
+        match (answer.next(), output.next()) {
+            (None, None) => return Ok(()),
+            (Some(a), Some(o)) => {
+                if !eq(o, a) {
+                    return Err(format!(
+                        "token {id}: expected {}, found {}",
+                        shorten(a),
+                        shorten(o)
+                    ));
+                }
+            }
+            (Some(a), None) => {
+                return Err(format!(
+                    "token {id}: expected {}, found end of file",
+                    shorten(a)
+                ));
+            }
+            (None, Some(o)) => {
+                return Err(format!(
+                    "token {id}: expected end of file, found {}",
+                    shorten(o)
+                ));
+            }
+        }
+    }
+    unreachable!()
+}
+
+/// Whether two floating-point tokens are equal within given tolerance
+fn float_eq(output: &str, answer: &str, absolute: f64, relative: f64) -> bool {
+    match (output.parse::<f64>(), answer.parse::<f64>()) {
+        (Ok(o), Ok(a)) => {
+            // Also handles infinities, whose difference is NaN
+            if o == a {
+                return true;
+            }
+            let diff = (o - a).abs();
+            diff <= absolute || diff <= relative * a.abs()
+        }
+        // Tokens that are not numbers must match exactly
+        _ => output == answer,
+    }
+}
+
+/// Check the output against the answer, returning the reason if they mismatch
+pub fn check(checker: &Checker, output: &str, answer: &str) -> Result<(), String> {
+    match *checker {
+        Checker::Standard => compare(&trim(output), &trim(answer)),
+        Checker::Strict => compare(
+            &output.split('\n').collect::<Vec<_>>(),
+            &answer.split('\n').collect::<Vec<_>>(),
+        ),
+        Checker::Token { case_insensitive } => compare_tokens(output, answer, |o, a| {
+            if case_insensitive {
+                o.to_lowercase() == a.to_lowercase()
+            } else {
+                o == a
+            }
+        }),
+        Checker::Float { absolute, relative } => {
+            compare_tokens(output, answer, |o, a| float_eq(o, a, absolute, relative))
+        }
+    }
+}
+
+/// Generate a unified diff from the answer to the output, truncated to keep it small
+///
+/// Outputs can be as large as the output limit, so the diff is computed under a deadline.
+pub fn diff(output: &str, answer: &str) -> String {
+    let mut diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(answer, output)
+        .unified_diff()
+        .header("answer", "output")
+        .to_string();
+    if diff.len() > MAX_DIFF_LEN {
+        let mut end = MAX_DIFF_LEN;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+        diff.push_str("\n... (truncated)\n");
+    }
+    diff
+}
+
+/// Parse a fraction of the score, which must be between 0 and 1
+fn parse_fraction(fraction: &str) -> Option<f64> {
+    let fraction = fraction.parse().ok()?;
+    (0.0..=1.0).contains(&fraction).then_some(fraction)
+}
+
+/// Parse the report of a special judge which prints the verdict on the first line
+///
+/// The verdict is `Accepted`, `Wrong Answer`, a fraction, or `points` followed by a fraction.
+fn parse_default(report: &str) -> Result<Verdict, String> {
+    let mut lines = report.lines();
+    let verdict = lines.next().unwrap_or_default().trim();
+    let fraction = match verdict {
+        "Accepted" => Some(1.0),
+        "Wrong Answer" => Some(0.0),
+        _ => parse_fraction(verdict.strip_prefix("points").unwrap_or(verdict).trim()),
+    }
+    .ok_or_else(|| format!("Invalid verdict from special judge: {verdict:?}"))?;
+    Ok(Verdict::new(fraction, lines.collect::<Vec<_>>().join("\n")))
+}
+
+/// Parse the exit status and the message of a testlib checker
+fn parse_testlib(status: ExitStatus, message: &str) -> Result<Verdict, String> {
+    let message = message.trim().to_string();
+    match status.code() {
+        Some(TESTLIB_OK) => Ok(Verdict::new(1.0, message)),
+        Some(TESTLIB_WA) => Ok(Verdict::new(0.0, message)),
+        Some(TESTLIB_PE) => Ok(Verdict {
+            result: JobResult::PresentationError,
+            fraction: 0.0,
+            info: message,
+        }),
+        Some(TESTLIB_FAIL) => Err(message),
+        // The message of `quitp` starts with `points` and the fraction earned
+        Some(TESTLIB_POINTS) => message
+            .strip_prefix("points")
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(parse_fraction)
+            .map(|fraction| Verdict::new(fraction, message.clone()))
+            .ok_or_else(|| format!("Invalid points from checker: {message}")),
+        Some(code) if (TESTLIB_PC_BASE..=TESTLIB_PC_BASE + 100).contains(&code) => Ok(
+            Verdict::new((code - TESTLIB_PC_BASE) as f64 / 100.0, message),
+        ),
+        _ => Err(format!("Checker failed: {status}\n{message}")),
+    }
+}
+
+/// Run a special judge on the output of a case
+///
+/// Returns the reason as an error if the special judge failed.
+pub fn special_judge(
+    command: &[String],
+    protocol: CheckerProtocol,
+    case: &Case,
+    output: &Path,
+    dir: &Path,
+) -> Result<Verdict, String> {
+    // Substitute %INPUT%, %OUTPUT% and %ANSWER% in args
+    let args: Vec<&str> = command
+        .iter()
+        .map(|arg| match arg.as_ref() {
+            "%INPUT%" => case.input_file.to_str().unwrap(),
+            "%OUTPUT%" => output.to_str().unwrap(),
+            "%ANSWER%" => case.answer_file.to_str().unwrap(),
+            _ => arg,
+        })
+        .collect();
+
+    // Save the report to a file, so that a verbose special judge can't block on a full pipe
+    let report_path = dir.join(".spj");
+    let report = File::create(&report_path)
+        .map_err(|err| format!("Unable to create special judge output file: {err}"))?;
+    // testlib checkers write their message to stderr
+    let (stdout, stderr) = match protocol {
+        CheckerProtocol::Default => (Stdio::from(report), Stdio::null()),
+        CheckerProtocol::Testlib => (Stdio::null(), Stdio::from(report)),
+    };
+    let mut child = Command::new(args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .map_err(|err| format!("Unable to spawn special judge: {err}"))?;
+    let usage = sandbox::wait(&mut child, Some(SPJ_TIME_LIMIT))
+        .map_err(|err| format!("Unknown error when executing special judge: {err}"))?;
+    if usage.killed {
+        return Err("Special judge timed out".to_string());
+    }
+
+    let report = fs::read_to_string(&report_path)
+        .map_err(|err| format!("Unable to read special judge output: {err}"))?;
+    match protocol {
+        CheckerProtocol::Default => {
+            if !usage.status.success() {
+                return Err(format!("Special judge failed: {}", usage.status));
+            }
+            parse_default(&report)
+        }
+        CheckerProtocol::Testlib => parse_testlib(usage.status, &report),
+    }
+}
+
+/// Programs of a problem that the judger compiles from source
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Program {
+    Checker,
+    Validator,
+    Reference,
+}
+
+impl Program {
+    fn name(self) -> &'static str {
+        match self {
+            Program::Checker => "checker",
+            Program::Validator => "validator",
+            Program::Reference => "reference",
+        }
+    }
+}
+
+/// Checkers and other programs compiled from source, kept for the lifetime of the judger
+pub struct Checkers {
+    dir: TempDir,
+    /// Path of the binary, or the compile error, for each program of each problem
+    binaries: Mutex<BTreeMap<(u32, Program), Result<PathBuf, String>>>,
+}
+
+impl Checkers {
+    pub fn new() -> Result<Self, io::Error> {
+        Ok(Self {
+            dir: TempDir::new()?,
+            binaries: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Get the special judge command of a problem, compiling its checker on first use
+    pub fn command(
+        &self,
+        problem: &Problem,
+        config: &Config,
+    ) -> Option<Result<Vec<String>, String>> {
+        if !matches!(problem.typ, ProblemType::Spj | ProblemType::AnswerOnly) {
+            return None;
+        }
+        let Some(source) = &problem.checker_source else {
+            return problem.special_judge().map(Ok);
+        };
+        let binary = self.binary(problem.id, Program::Checker, source, config);
+        Some(binary.map(|binary| {
+            vec![
+                binary.to_str().unwrap().to_string(),
+                "%INPUT%".to_string(),
+                "%OUTPUT%".to_string(),
+                "%ANSWER%".to_string(),
+            ]
+        }))
+    }
+
+    /// Get the binary of a program of a problem, compiling it on first use
+    pub fn binary(
+        &self,
+        id: u32,
+        program: Program,
+        source: &CheckerSource,
+        config: &Config,
+    ) -> Result<PathBuf, String> {
+        let mut binaries = self.binaries.lock().unwrap();
+        binaries
+            .entry((id, program))
+            .or_insert_with(|| self.compile(id, program, source, config))
+            .clone()
+    }
+
+    /// Compile a program of a problem
+    fn compile(
+        &self,
+        id: u32,
+        program: Program,
+        source: &CheckerSource,
+        config: &Config,
+    ) -> Result<PathBuf, String> {
+        let name = program.name();
+        let dir = self.dir.path().join(format!("problem{id}")).join(name);
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("Unable to create {name} directory: {err}"))?;
+
+        // Copy the source and other files to the compile directory
+        let mut copied = vec![];
+        for file in std::iter::once(&source.source).chain(&source.files) {
+            let path = file.file_name().map(|name| dir.join(name));
+            match path.map(|path| fs::copy(file, &path).map(|_| path)) {
+                Some(Ok(path)) => copied.push(path),
+                _ => return Err(format!("Unable to copy {name} file: {}", file.display())),
+            }
+        }
+
+        // Substitute %INPUT% and %OUTPUT% in args
+        let lang = config.get_lang(&source.language).unwrap();
+        let binary = dir.join(name);
+        let args: Vec<&str> = lang
+            .command
+            .iter()
+            .map(|arg| match arg.as_ref() {
+                "%INPUT%" => copied[0].to_str().unwrap(),
+                "%OUTPUT%" => binary.to_str().unwrap(),
+                _ => arg,
+            })
+            .collect();
+
+        let output = Command::new(args[0])
+            .args(&args[1..])
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| format!("Unable to compile {name}: {err}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Compilation error of {name}:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(binary)
+    }
+}
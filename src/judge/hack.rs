@@ -0,0 +1,308 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use temp_dir::TempDir;
+
+use super::checker::{Checkers, Program, SPJ_TIME_LIMIT};
+use super::{cache::Cache, compile, run_case, sandbox, Task};
+use crate::api::err::Error;
+use crate::api::hacks::HackState;
+use crate::api::jobs::{CaseResult, Job, JobResult, JobStatus};
+use crate::api::problems::max_limit;
+use crate::config::{Case, Config, Problem};
+use crate::persistent::models::{self, Hack};
+
+/// Longest message of a validator kept in the info of a hack
+const MAX_INFO_LEN: usize = 1024;
+
+/// Run a program of a problem on an input, writing its stdout and stderr to files
+///
+/// Returns whether the program exited successfully.
+fn run(program: &Path, input: &Path, stdout: &Path, stderr: &Path) -> Result<bool, String> {
+    let open = |path: &Path| {
+        File::create(path).map_err(|err| format!("Unable to create {}: {err}", path.display()))
+    };
+    let mut child = Command::new(program)
+        .stdin(File::open(input).map_err(|err| format!("Unable to open input file: {err}"))?)
+        .stdout(open(stdout)?)
+        .stderr(open(stderr)?)
+        .spawn()
+        .map_err(|err| format!("Unable to spawn {}: {err}", program.display()))?;
+    let usage = sandbox::wait(&mut child, Some(SPJ_TIME_LIMIT))
+        .map_err(|err| format!("Unknown error when executing {}: {err}", program.display()))?;
+    if usage.killed {
+        return Err(format!("{} timed out", program.display()));
+    }
+    Ok(usage.status.success())
+}
+
+/// Take the output of the reference solution on a hack input as the answer
+///
+/// Returns a case on the input, under the most generous limits among the cases of the problem,
+/// which must have some.
+fn hack_case(
+    config: &Config,
+    checkers: &Checkers,
+    problem: &Problem,
+    input: PathBuf,
+    dir: &Path,
+) -> Result<Case, String> {
+    let (Some(reference), Some(case)) = (&problem.reference, problem.cases.first()) else {
+        return Err(format!("Problem {} can't be hacked", problem.id));
+    };
+    let answer = dir.join("answer");
+    let answered = checkers
+        .binary(problem.id, Program::Reference, reference, config)
+        .and_then(|reference| run(&reference, &input, &answer, &dir.join("log")))?;
+    if !answered {
+        return Err("Reference solution failed on the input".to_string());
+    }
+    let cases = problem.cases.iter();
+    Ok(Case {
+        input_file: input,
+        answer_file: answer,
+        time_limit: max_limit(cases.clone().map(|case| case.time_limit)),
+        wall_time_limit: Some(max_limit(cases.clone().map(|case| {
+            case.wall_time_limit()
+                .map_or(0, |limit| limit.as_micros() as u32)
+        }))),
+        memory_limit: max_limit(cases.clone().map(|case| case.memory_limit)),
+        output_limit: Some(max_limit(cases.map(|case| problem.output_limit(case)))),
+        show_diff: false,
+        sample: false,
+        ..case.clone()
+    })
+}
+
+/// Judge a job on the input of a successful hack against it, as an extra case
+pub fn judge_hack(
+    task: &Task,
+    config: &Config,
+    checkers: &Checkers,
+    hack: &Hack,
+    id: u32,
+) -> CaseResult {
+    let target = task.target;
+    let mut case_result = CaseResult::new(id, JobResult::Waiting);
+
+    // The input and the answer are kept out of the working directory of the program
+    let dir = task.dir.join(format!("case{id}"));
+    let case_dir = dir.join("run");
+    let input = dir.join("input");
+    let case = fs::create_dir_all(&case_dir)
+        .and_then(|_| fs::write(&input, &hack.input))
+        .map_err(|err| format!("Unable to prepare hack {}: {err}", hack.id))
+        .and_then(|_| hack_case(config, checkers, task.problem, input, &dir));
+    let case_result = match case {
+        Ok(case) => run_case(task, &case, &case_dir, case_result).0,
+        Err(err) => {
+            log::error!(target: target, "{err}");
+            case_result.result = JobResult::SystemError;
+            case_result
+        }
+    };
+    let _ = fs::remove_dir_all(&dir);
+    case_result
+}
+
+/// Judge a hack, and count the verdict against the target if it fails on the input
+///
+/// The input must pass the validator of the problem, and the output of the reference solution
+/// on it is taken as the answer.
+pub fn hack(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    checkers: &Checkers,
+    cache: &Cache,
+    name: &str,
+    hid: i32,
+) {
+    let target = &format!("{name}@hack{hid}");
+
+    let mut hack = models::get_hack(conn, hid).unwrap();
+    let job: Job = models::get_job(conn, hack.job_id).unwrap().into();
+    let problem = config.get_problem(job.submission.problem_id).unwrap();
+    log::info!(
+        target: target,
+        "New hack task started, job id: {}, problem id: {}",
+        job.id,
+        problem.id
+    );
+
+    // Push update to database
+    macro_rules! push {
+        () => {
+            hack.updated_time = Utc::now().naive_utc();
+            if let Err(err) = models::update_hack(conn, hack.clone()) {
+                log::error!(target: target, "Exiting due to err: {err}");
+                process::exit(0);
+            }
+        };
+    }
+
+    // Auxiliary macro for finishing the hack with a state and some info
+    macro_rules! finish {
+        ($state: expr, $info: expr) => {
+            hack.hack_state = $state;
+            hack.info = $info;
+            log::info!(target: target, "Hack ended: {:?}, {}", hack.hack_state, hack.info);
+            push!();
+            return;
+        };
+    }
+
+    hack.hack_state = HackState::Running;
+    push!();
+
+    // The target may have been hacked or rejudged in the meantime
+    if job.state != JobStatus::Finished || job.result != JobResult::Accepted {
+        finish!(
+            HackState::Invalid,
+            format!("Job {} is no longer accepted", job.id)
+        );
+    }
+    let (Some(validator), Some(_)) = (&problem.validator, &problem.reference) else {
+        finish!(
+            HackState::SystemError,
+            format!("Problem {} can't be hacked", problem.id)
+        );
+    };
+
+    // Create a temp directory for use
+    let dir = TempDir::new().unwrap();
+    let input = dir.child("input");
+    let log = dir.child("log");
+    if let Err(err) = fs::write(&input, &hack.input) {
+        finish!(
+            HackState::SystemError,
+            format!("Unable to save input: {err}")
+        );
+    }
+
+    // Validate the input
+    let valid = checkers
+        .binary(problem.id, Program::Validator, validator, config)
+        .and_then(|validator| run(&validator, &input, &dir.child("validator"), &log));
+    match valid {
+        Ok(true) => (),
+        Ok(false) => {
+            let info = fs::read_to_string(&log).unwrap_or_default();
+            let info = info.trim_end();
+            // Cut at a character boundary, so the message stays valid UTF-8
+            let len = info
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain([info.len()])
+                .take_while(|&index| index <= MAX_INFO_LEN)
+                .last()
+                .unwrap_or(0);
+            finish!(HackState::Invalid, info[..len].to_string());
+        }
+        Err(err) => {
+            finish!(HackState::SystemError, err);
+        }
+    }
+
+    // Take the output of the reference solution as the answer
+    let case = match hack_case(config, checkers, problem, input, dir.path()) {
+        Ok(case) => case,
+        Err(err) => {
+            finish!(HackState::SystemError, err);
+        }
+    };
+
+    // Compile the target, which is most likely cached
+    let target_dir = dir.child("target");
+    let compiled = fs::create_dir(&target_dir)
+        .map_err(|err| format!("Unable to create target directory: {err}"))
+        .and_then(|_| compile(config, cache, problem, &job.submission, &target_dir, target));
+    let exec = match compiled {
+        Ok((compiled, exec)) if compiled.success => exec,
+        Ok(_) => {
+            finish!(
+                HackState::SystemError,
+                "Target no longer compiles".to_string()
+            );
+        }
+        Err(err) => {
+            finish!(HackState::SystemError, err);
+        }
+    };
+
+    // Run the target as on an extra case after those it has been judged on
+    let case_dir = dir.child("case");
+    if let Err(err) = fs::create_dir(&case_dir) {
+        finish!(
+            HackState::SystemError,
+            format!("Unable to create working directory: {err}")
+        );
+    }
+    let special_judge = checkers.command(problem, config);
+    let task = Task {
+        target,
+        problem,
+        dir: dir.path(),
+        exec: Some(&exec),
+        answers: None,
+        special_judge: &special_judge,
+    };
+    let (case_result, _) = run_case(
+        &task,
+        &case,
+        &case_dir,
+        CaseResult::new(job.cases.len() as u32, JobResult::Waiting),
+    );
+
+    match case_result.result {
+        JobResult::Accepted => {
+            finish!(HackState::Unsuccessful, case_result.info);
+        }
+        JobResult::SystemError | JobResult::SpjError => {
+            finish!(HackState::SystemError, case_result.info);
+        }
+        result => {
+            // The target fails on the input, so it is no longer accepted, even after a rejudge.
+            // The job may have been hacked or rejudged in the meantime, so it is checked again
+            // in the same transaction as the update, for a verdict to count only once
+            let cases = job.cases.len();
+            let updated = conn.immediate_transaction(|conn| {
+                let mut job: Job = models::get_job(conn, hack.job_id)?.into();
+                if job.state != JobStatus::Finished
+                    || job.result != JobResult::Accepted
+                    || job.cases.len() != cases
+                {
+                    return Ok(false);
+                }
+                job.result = result;
+                job.score = 0.0;
+                job.cases.push(case_result.clone());
+                job.updated_time = Utc::now();
+                models::update_job(conn, job.into())?;
+
+                hack.hack_state = HackState::Successful;
+                hack.info = case_result.info.clone();
+                hack.updated_time = Utc::now().naive_utc();
+                models::update_hack(conn, hack.clone())?;
+                Ok::<_, Error>(true)
+            });
+            match updated {
+                Ok(true) => {
+                    log::info!(target: target, "Hack ended: {:?}, {}", hack.hack_state, hack.info);
+                }
+                Ok(false) => {
+                    finish!(
+                        HackState::Invalid,
+                        format!("Job {} is no longer accepted", job.id)
+                    );
+                }
+                Err(err) => {
+                    log::error!(target: target, "Exiting due to err: {err}");
+                    process::exit(0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval between two checks on a running child process
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Resource usage of an exited child process
+pub struct Usage {
+    pub status: ExitStatus,
+    /// User and system CPU time
+    pub cpu_time: Duration,
+    pub wall_time: Duration,
+    /// Peak resident set size in bytes
+    pub memory: u64,
+    /// Whether the child was killed for exceeding the wall clock time limit
+    pub killed: bool,
+}
+
+/// Set a resource limit on the child process before it executes
+fn set_rlimit(command: &mut Command, resource: libc::__rlimit_resource_t, limit: u64) {
+    let limit = libc::rlimit {
+        rlim_cur: limit,
+        rlim_max: limit,
+    };
+    // SAFETY: only async-signal-safe functions are called between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(resource, &limit) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Limit the size of files written by the child process, 0 for unlimited
+///
+/// The child is killed by `SIGXFSZ` once it writes more than `limit` bytes to a file.
+pub fn limit_output(command: &mut Command, limit: u64) {
+    if limit != 0 {
+        // One more byte so that an output of exactly `limit` bytes is allowed
+        set_rlimit(command, libc::RLIMIT_FSIZE, limit + 1);
+    }
+}
+
+/// Limit the CPU time of the child process
+///
+/// The limit is rounded up to whole seconds with some slack, so it only
+/// serves to stop busy loops. The precise check is done on the reported usage.
+pub fn limit_cpu_time(command: &mut Command, limit: Duration) {
+    let secs = limit.as_secs() + 2;
+    set_rlimit(command, libc::RLIMIT_CPU, secs);
+}
+
+/// Convert a `timeval` to a `Duration`
+fn duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+/// Reap the child process, returning its status and resource usage if it has exited
+fn wait4(child: &Child, options: i32) -> io::Result<Option<(ExitStatus, libc::rusage)>> {
+    let mut status = 0;
+    // SAFETY: rusage is plain old data which is filled by wait4
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    match unsafe { libc::wait4(child.id() as i32, &mut status, options, &mut rusage) } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(None),
+        _ => Ok(Some((ExitStatus::from_raw(status), rusage))),
+    }
+}
+
+/// Wait for the child process to exit, killing it once the wall clock time limit is exceeded
+pub fn wait(child: &mut Child, wall_time_limit: Option<Duration>) -> io::Result<Usage> {
+    let now = Instant::now();
+    let mut killed = false;
+    let (status, rusage) = loop {
+        if let Some(result) = wait4(child, libc::WNOHANG)? {
+            break result;
+        }
+        if wall_time_limit.is_some_and(|limit| now.elapsed() > limit) {
+            child.kill()?;
+            killed = true;
+            // Now the child is sure to exit soon
+            if let Some(result) = wait4(child, 0)? {
+                break result;
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    Ok(Usage {
+        status,
+        cpu_time: duration(rusage.ru_utime) + duration(rusage.ru_stime),
+        wall_time: now.elapsed(),
+        memory: rusage.ru_maxrss as u64 * 1024,
+        killed,
+    })
+}
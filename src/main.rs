@@ -30,6 +30,7 @@ mod authorization;
 mod config;
 mod judge;
 mod persistent;
+mod rating;
 
 use api::err::{Error, Reason};
 use config::Args;
@@ -86,6 +87,8 @@ async fn main() -> std::io::Result<()> {
         log::info!("Flushing persistent data");
         // It's ok that the database doesn't exist
         let _ = std::fs::remove_file(DB_URL);
+        // Compile results are kept in the cache
+        let _ = std::fs::remove_dir_all(&config.judger.cache_dir);
     }
 
     // Run migrations
@@ -163,14 +166,36 @@ async fn main() -> std::io::Result<()> {
                     .service(api::jobs::new_job)
                     .service(api::jobs::get_jobs)
                     .service(api::jobs::get_job)
+                    .service(api::problems::get_problems)
+                    .service(api::problems::get_problem)
                     .service(api::users::get_users)
+                    .service(api::ratings::get_user_rating)
                     .service(api::contests::get_contests)
                     .service(api::contests::get_contest)
                     .service(api::contests::get_rank_list)
+                    .service(api::standings::get_rank_list_csv)
+                    .service(api::standings::get_event_feed)
+                    .service(api::registrations::register)
+                    .service(api::registrations::withdraw)
+                    .service(api::participations::start)
+                    .service(api::participations::start_virtual)
+                    .service(api::teams::new_team)
+                    .service(api::teams::get_contest_teams)
+                    .service(api::clarifications::ask)
+                    .service(api::clarifications::get_clarifications)
+                    .service(api::announcements::get_announcements)
+                    .service(api::hacks::new_hack)
+                    .service(api::hacks::get_hacks)
+                    .service(api::hacks::get_hack)
                     // Services that only author or admin can access
                     .service(api::jobs::rejudge_job)
                     .service(api::jobs::cancel_job)
                     .service(api::contests::update_contest)
+                    .service(api::registrations::get_registrations)
+                    .service(api::registrations::review_registration)
+                    .service(api::clarifications::answer)
+                    .service(api::announcements::announce)
+                    .service(api::ratings::finalize)
                     // Services that only admin can access
                     .service(authorization::privilege)
                     .service(api::users::update_user),
@@ -207,12 +232,34 @@ async fn main() -> std::io::Result<()> {
             .service(api::jobs::get_job)
             .service(api::jobs::rejudge_job)
             .service(api::jobs::cancel_job)
+            .service(api::problems::get_problems)
+            .service(api::problems::get_problem)
             .service(api::users::update_user)
             .service(api::users::get_users)
+            .service(api::ratings::get_user_rating)
             .service(api::contests::update_contest)
             .service(api::contests::get_contests)
             .service(api::contests::get_contest)
             .service(api::contests::get_rank_list)
+            .service(api::standings::get_rank_list_csv)
+            .service(api::standings::get_event_feed)
+            .service(api::registrations::register)
+            .service(api::registrations::withdraw)
+            .service(api::participations::start)
+            .service(api::participations::start_virtual)
+            .service(api::teams::new_team)
+            .service(api::teams::get_contest_teams)
+            .service(api::clarifications::ask)
+            .service(api::clarifications::get_clarifications)
+            .service(api::clarifications::answer)
+            .service(api::announcements::announce)
+            .service(api::announcements::get_announcements)
+            .service(api::hacks::new_hack)
+            .service(api::hacks::get_hacks)
+            .service(api::hacks::get_hack)
+            .service(api::ratings::finalize)
+            .service(api::registrations::get_registrations)
+            .service(api::registrations::review_registration)
             // DO NOT REMOVE: used in automatic testing
             .service(exit)
     })
@@ -1,9 +1,23 @@
 //! This module simply re-exports its submodules.
 
+mod announcements;
+mod clarifications;
 mod contests;
+mod hacks;
 mod jobs;
+mod participations;
+mod ratings;
+mod registrations;
+mod teams;
 mod users;
 
+pub use announcements::*;
+pub use clarifications::*;
 pub use contests::*;
+pub use hacks::*;
 pub use jobs::*;
+pub use participations::*;
+pub use ratings::*;
+pub use registrations::*;
+pub use teams::*;
 pub use users::*;
@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::api::err::Error;
+use crate::persistent::schema::announcements;
+
+#[derive(Clone, Queryable, Insertable, Identifiable)]
+pub struct Announcement {
+    pub id: i32,
+    pub contest_id: i32,
+    pub problem_id: Option<i32>,
+    pub content: String,
+    pub created_time: NaiveDateTime,
+}
+
+/// Get announcements count
+pub fn announcements_count(conn: &mut SqliteConnection) -> Result<i32, Error> {
+    use self::announcements::dsl::*;
+
+    let count: i64 = announcements.count().get_result(conn)?;
+
+    Ok(count as i32)
+}
+
+/// Get the announcements in a contest published after a time if given, in the order of publishing
+pub fn get_announcements(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    since: Option<NaiveDateTime>,
+) -> Result<Vec<Announcement>, Error> {
+    use self::announcements::dsl::*;
+
+    let mut query = announcements.filter(contest_id.eq(cid)).into_boxed();
+    if let Some(since) = since {
+        query = query.filter(created_time.gt(since));
+    }
+    Ok(query.order(id.asc()).load(conn)?)
+}
+
+pub fn new_announcement(
+    conn: &mut SqliteConnection,
+    announcement: Announcement,
+) -> Result<Announcement, Error> {
+    use self::announcements::dsl::*;
+
+    diesel::insert_into(announcements)
+        .values(announcement.clone())
+        .execute(conn)?;
+    Ok(announcement)
+}
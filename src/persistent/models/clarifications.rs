@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::api::err::{Error, Reason};
+use crate::persistent::schema::clarifications;
+
+#[derive(Clone, Queryable, Insertable, AsChangeset, Identifiable)]
+#[diesel(treat_none_as_null = true)]
+pub struct Clarification {
+    pub id: i32,
+    pub contest_id: i32,
+    pub problem_id: Option<i32>,
+    pub user_id: i32,
+    pub question: String,
+    pub answer: Option<String>,
+    pub public: bool,
+    pub created_time: NaiveDateTime,
+    pub updated_time: NaiveDateTime,
+}
+
+/// Get clarifications count
+pub fn clarifications_count(conn: &mut SqliteConnection) -> Result<i32, Error> {
+    use self::clarifications::dsl::*;
+
+    let count: i64 = clarifications.count().get_result(conn)?;
+
+    Ok(count as i32)
+}
+
+/// Get a clarification in a contest
+pub fn get_clarification(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    clid: i32,
+) -> Result<Clarification, Error> {
+    use self::clarifications::dsl::*;
+
+    clarifications
+        .find(clid)
+        .filter(contest_id.eq(cid))
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| {
+            Error::new(
+                Reason::NotFound,
+                format!("Clarification {clid} not found in contest {cid}."),
+            )
+        })
+}
+
+/// Get the clarifications in a contest updated after a time if given, in the order they were asked
+pub fn get_clarifications(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    since: Option<NaiveDateTime>,
+) -> Result<Vec<Clarification>, Error> {
+    use self::clarifications::dsl::*;
+
+    let mut query = clarifications.filter(contest_id.eq(cid)).into_boxed();
+    if let Some(since) = since {
+        query = query.filter(updated_time.gt(since));
+    }
+    Ok(query.order(id.asc()).load(conn)?)
+}
+
+pub fn new_clarification(
+    conn: &mut SqliteConnection,
+    clarification: Clarification,
+) -> Result<Clarification, Error> {
+    use self::clarifications::dsl::*;
+
+    diesel::insert_into(clarifications)
+        .values(clarification.clone())
+        .execute(conn)?;
+    Ok(clarification)
+}
+
+pub fn update_clarification(
+    conn: &mut SqliteConnection,
+    clarification: Clarification,
+) -> Result<Clarification, Error> {
+    Ok(clarification.save_changes(conn)?)
+}
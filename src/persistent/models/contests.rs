@@ -2,6 +2,7 @@ use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use serde::Serialize;
 
+use crate::api::contests::{ChallengePhase, ContestProblems, RegistrationRule};
 use crate::api::err::{Error, Reason};
 use crate::persistent::schema::contests;
 
@@ -14,6 +15,14 @@ pub struct Contest {
     pub problem_ids: String,
     pub user_ids: String,
     pub submission_limit: i32,
+    pub stop_on_failure: bool,
+    pub hide_case_results: bool,
+    pub registration: Option<RegistrationRule>,
+    pub allow_upsolving: bool,
+    pub duration: Option<i32>,
+    pub problem_entries: ContestProblems,
+    pub challenge_phase: Option<ChallengePhase>,
+    pub rated: bool,
 }
 
 impl From<crate::api::contests::Contest> for Contest {
@@ -36,6 +45,14 @@ impl From<crate::api::contests::Contest> for Contest {
                 .collect::<Vec<_>>()
                 .join(","),
             submission_limit: contest.submission_limit as i32,
+            stop_on_failure: contest.stop_on_failure,
+            hide_case_results: contest.hide_case_results,
+            registration: contest.registration,
+            allow_upsolving: contest.allow_upsolving,
+            duration: contest.duration.map(|duration| duration as i32),
+            problem_entries: contest.problems,
+            challenge_phase: contest.challenge_phase,
+            rated: contest.rated,
         }
     }
 }
@@ -90,3 +107,19 @@ pub fn new_contest(conn: &mut SqliteConnection, con: Contest) -> Result<Contest,
 pub fn update_contest(conn: &mut SqliteConnection, con: Contest) -> Result<Contest, Error> {
     Ok(con.save_changes(conn)?)
 }
+
+/// Set the users of a contest, leaving everything else untouched
+pub fn set_contest_users(conn: &mut SqliteConnection, cid: i32, uids: &[u32]) -> Result<(), Error> {
+    use self::contests::dsl::*;
+
+    diesel::update(contests.find(cid))
+        .set(
+            user_ids.eq(uids
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")),
+        )
+        .execute(conn)?;
+    Ok(())
+}
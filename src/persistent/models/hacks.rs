@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::Deserialize;
+
+use crate::api::err::{Error, Reason};
+use crate::api::hacks::HackState;
+use crate::persistent::schema::hacks;
+
+#[derive(Clone, Queryable, Insertable, AsChangeset, Identifiable)]
+pub struct Hack {
+    pub id: i32,
+    pub contest_id: i32,
+    pub job_id: i32,
+    pub user_id: i32,
+    pub input: String,
+    pub hack_state: HackState,
+    pub info: String,
+    pub created_time: NaiveDateTime,
+    pub updated_time: NaiveDateTime,
+}
+
+#[derive(Default, Deserialize)]
+pub struct HackFilter {
+    pub contest_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub job_id: Option<i32>,
+    pub state: Option<HackState>,
+}
+
+/// Get hacks count
+pub fn hacks_count(conn: &mut SqliteConnection) -> Result<i32, Error> {
+    use self::hacks::dsl::*;
+
+    let count: i64 = hacks.count().get_result(conn)?;
+
+    Ok(count as i32)
+}
+
+/// Get specific hack
+pub fn get_hack(conn: &mut SqliteConnection, hid: i32) -> Result<Hack, Error> {
+    use self::hacks::dsl::*;
+
+    hacks
+        .find(hid)
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| Error::new(Reason::NotFound, format!("Hack {hid} not found.")))
+}
+
+/// Get filtered hacks, in the order they were made
+pub fn get_hacks(conn: &mut SqliteConnection, filt: HackFilter) -> Result<Vec<Hack>, Error> {
+    use self::hacks::dsl::*;
+
+    let mut query = hacks.into_boxed();
+    if let Some(cid) = filt.contest_id {
+        query = query.filter(contest_id.eq(cid));
+    }
+    if let Some(uid) = filt.user_id {
+        query = query.filter(user_id.eq(uid));
+    }
+    if let Some(jid) = filt.job_id {
+        query = query.filter(job_id.eq(jid));
+    }
+    if let Some(state) = filt.state {
+        query = query.filter(hack_state.eq(state));
+    }
+    Ok(query.order(id.asc()).load(conn)?)
+}
+
+/// Count the hacks of some users in a contest in a state, made no later than a time if given
+pub fn count_hacks(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    uids: &[i32],
+    state: HackState,
+    until: Option<NaiveDateTime>,
+) -> Result<i64, Error> {
+    use self::hacks::dsl::*;
+
+    let mut query = hacks
+        .filter(contest_id.eq(cid))
+        .filter(user_id.eq_any(uids))
+        .filter(hack_state.eq(state))
+        .into_boxed();
+    if let Some(until) = until {
+        query = query.filter(created_time.le(until));
+    }
+    Ok(query.count().get_result(conn)?)
+}
+
+pub fn new_hack(conn: &mut SqliteConnection, hack: Hack) -> Result<Hack, Error> {
+    use self::hacks::dsl::*;
+
+    diesel::insert_into(hacks)
+        .values(hack.clone())
+        .execute(conn)?;
+    Ok(hack)
+}
+
+pub fn update_hack(conn: &mut SqliteConnection, hack: Hack) -> Result<Hack, Error> {
+    Ok(hack.save_changes(conn)?)
+}
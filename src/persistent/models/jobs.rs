@@ -4,7 +4,7 @@ use diesel::prelude::*;
 use serde::Deserialize;
 
 use crate::api::err::{Error, Reason};
-use crate::api::jobs::{CaseResults, JobResult, JobStatus};
+use crate::api::jobs::{Answers, CaseResults, JobResult, JobStatus, SourceFiles, SubmissionKind};
 use crate::persistent::schema::jobs;
 
 #[derive(Clone, Queryable, Insertable, AsChangeset, Identifiable)]
@@ -17,10 +17,14 @@ pub struct Job {
     pub user_id: i32,
     pub contest_id: i32,
     pub problem_id: i32,
+    pub submission_kind: SubmissionKind,
     pub job_state: JobStatus,
     pub result: JobResult,
     pub score: f64,
     pub cases: CaseResults,
+    pub source_files: Option<SourceFiles>,
+    pub answers: Option<Answers>,
+    pub team_id: Option<i32>,
 }
 
 /// We need to convert between api::jobs::Job and persistent::models::Job
@@ -38,10 +42,14 @@ impl From<crate::api::jobs::Job> for Job {
             user_id: job.submission.user_id as i32,
             contest_id: job.submission.contest_id as i32,
             problem_id: job.submission.problem_id as i32,
+            submission_kind: job.kind,
             job_state: job.state,
             result: job.result,
             score: job.score,
             cases: CaseResults(job.cases),
+            source_files: job.submission.files.map(SourceFiles),
+            answers: job.submission.answers.map(Answers),
+            team_id: job.team_id.map(|team_id| team_id as i32),
         }
     }
 }
@@ -135,19 +143,60 @@ pub fn get_jobs(conn: &mut SqliteConnection, filt: JobFilter) -> Result<Vec<Job>
     Ok(query.load(conn)?)
 }
 
+/// Which submissions of a user in a contest are taken into account
+pub struct SubmissionScope {
+    pub kinds: Vec<SubmissionKind>,
+    /// Only submissions made no later than this, if given
+    pub until: Option<NaiveDateTime>,
+    /// Submissions of the whole team instead of the user, if given
+    pub team: Option<i32>,
+}
+
+impl SubmissionScope {
+    /// All submissions of a kind
+    pub fn all(kind: SubmissionKind) -> Self {
+        Self {
+            kinds: vec![kind],
+            until: None,
+            team: None,
+        }
+    }
+}
+
+/// Get the submissions of a user, or their team, on a problem in a contest within a scope
+fn scoped_submissions<'a>(
+    uid: i32,
+    pid: i32,
+    cid: i32,
+    scope: &SubmissionScope,
+) -> jobs::BoxedQuery<'a, diesel::sqlite::Sqlite> {
+    use self::jobs::dsl::*;
+
+    let mut query = match scope.team {
+        Some(tid) => jobs.filter(team_id.eq(tid)).into_boxed(),
+        None => jobs.filter(user_id.eq(uid)).into_boxed(),
+    };
+    query = query
+        .filter(problem_id.eq(pid))
+        .filter(contest_id.eq(cid))
+        .filter(submission_kind.eq_any(scope.kinds.clone()));
+    if let Some(until) = scope.until {
+        query = query.filter(created_time.le(until));
+    }
+    query
+}
+
 /// Get the latest submission of a user on a problem in a contest
 pub fn get_latest_submission(
     conn: &mut SqliteConnection,
     uid: i32,
     pid: i32,
     cid: i32,
+    scope: &SubmissionScope,
 ) -> Result<Option<Job>, Error> {
     use self::jobs::dsl::*;
 
-    Ok(jobs
-        .filter(user_id.eq(uid))
-        .filter(problem_id.eq(pid))
-        .filter(contest_id.eq(cid))
+    Ok(scoped_submissions(uid, pid, cid, scope)
         .order(created_time.desc())
         .first(conn)
         .optional()?)
@@ -159,33 +208,60 @@ pub fn get_highest_submission(
     uid: i32,
     pid: i32,
     cid: i32,
+    scope: &SubmissionScope,
 ) -> Result<Option<Job>, Error> {
     use self::jobs::dsl::*;
 
-    Ok(jobs
-        .filter(user_id.eq(uid))
-        .filter(problem_id.eq(pid))
-        .filter(contest_id.eq(cid))
+    Ok(scoped_submissions(uid, pid, cid, scope)
         .order((score.desc(), created_time))
         .first(conn)
         .optional()?)
 }
 
+/// Get the submissions of a user on a problem in a contest, in the order they were made
+pub fn get_submissions(
+    conn: &mut SqliteConnection,
+    uid: i32,
+    pid: i32,
+    cid: i32,
+    scope: &SubmissionScope,
+) -> Result<Vec<Job>, Error> {
+    use self::jobs::dsl::*;
+
+    Ok(scoped_submissions(uid, pid, cid, scope)
+        .order((created_time, id))
+        .load(conn)?)
+}
+
 /// Get the count of submissions on a problem of a user in a contest
 pub fn get_submission_count(
     conn: &mut SqliteConnection,
     uid: i32,
     pid: i32,
     cid: i32,
+    scope: &SubmissionScope,
 ) -> Result<i64, Error> {
+    Ok(scoped_submissions(uid, pid, cid, scope)
+        .count()
+        .get_result(conn)?)
+}
+
+/// Returns if a user has made any submission of the given kinds in a contest
+pub fn has_submissions(
+    conn: &mut SqliteConnection,
+    uid: i32,
+    cid: i32,
+    kinds: &[SubmissionKind],
+) -> Result<bool, Error> {
     use self::jobs::dsl::*;
 
-    Ok(jobs
+    let count: i64 = jobs
         .filter(user_id.eq(uid))
-        .filter(problem_id.eq(pid))
         .filter(contest_id.eq(cid))
+        .filter(submission_kind.eq_any(kinds))
         .count()
-        .get_result(conn)?)
+        .get_result(conn)?;
+    Ok(count > 0)
 }
 
 /// Update an existing job
@@ -0,0 +1,51 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::api::{err::Error, jobs::SubmissionKind};
+use crate::persistent::schema::participations;
+
+#[derive(Clone, Queryable, Insertable, Identifiable)]
+#[diesel(primary_key(contest_id, user_id))]
+pub struct Participation {
+    pub contest_id: i32,
+    pub user_id: i32,
+    pub start_time: NaiveDateTime,
+    /// `Official` for a personal window in the contest, `Virtual` after it ends
+    pub participation_kind: SubmissionKind,
+}
+
+/// Get the participation of a user in a contest, if any
+pub fn find_participation(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    uid: i32,
+) -> Result<Option<Participation>, Error> {
+    use self::participations::dsl::*;
+
+    Ok(participations.find((cid, uid)).first(conn).optional()?)
+}
+
+/// Get all participations in a contest, in the order they started
+pub fn get_participations(
+    conn: &mut SqliteConnection,
+    cid: i32,
+) -> Result<Vec<Participation>, Error> {
+    use self::participations::dsl::*;
+
+    Ok(participations
+        .filter(contest_id.eq(cid))
+        .order((start_time.asc(), user_id.asc()))
+        .load(conn)?)
+}
+
+pub fn new_participation(
+    conn: &mut SqliteConnection,
+    participation: Participation,
+) -> Result<Participation, Error> {
+    use self::participations::dsl::*;
+
+    diesel::insert_into(participations)
+        .values(participation.clone())
+        .execute(conn)?;
+    Ok(participation)
+}
@@ -0,0 +1,50 @@
+use diesel::prelude::*;
+
+use crate::api::err::Error;
+use crate::persistent::schema::ratings;
+
+/// The rank and rating change of a user in a finalized contest
+#[derive(Clone, Queryable, Insertable, AsChangeset, Identifiable)]
+#[diesel(primary_key(contest_id, user_id))]
+pub struct Rating {
+    pub contest_id: i32,
+    pub user_id: i32,
+    pub user_rank: i32,
+    pub old_rating: i32,
+    pub new_rating: i32,
+}
+
+/// Get the ratings in all finalized contests
+pub fn get_ratings(conn: &mut SqliteConnection) -> Result<Vec<Rating>, Error> {
+    use self::ratings::dsl::*;
+
+    Ok(ratings
+        .order((contest_id.asc(), user_rank.asc()))
+        .load(conn)?)
+}
+
+/// Get the ratings of a user in the finalized contests they took part in
+pub fn get_user_ratings(conn: &mut SqliteConnection, uid: i32) -> Result<Vec<Rating>, Error> {
+    use self::ratings::dsl::*;
+
+    Ok(ratings.filter(user_id.eq(uid)).load(conn)?)
+}
+
+/// Replace the ratings in a contest
+pub fn set_ratings(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    rating_forms: Vec<Rating>,
+) -> Result<(), Error> {
+    use self::ratings::dsl::*;
+
+    diesel::delete(ratings.filter(contest_id.eq(cid))).execute(conn)?;
+    diesel::insert_into(ratings)
+        .values(rating_forms)
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn update_rating(conn: &mut SqliteConnection, rating: Rating) -> Result<Rating, Error> {
+    Ok(rating.save_changes(conn)?)
+}
@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::api::err::{Error, Reason};
+use crate::api::registrations::RegistrationState;
+use crate::persistent::schema::registrations;
+
+#[derive(Clone, Queryable, Insertable, AsChangeset, Identifiable)]
+#[diesel(primary_key(contest_id, user_id))]
+pub struct Registration {
+    pub contest_id: i32,
+    pub user_id: i32,
+    pub registration_state: RegistrationState,
+    pub created_time: NaiveDateTime,
+}
+
+impl From<crate::api::registrations::Registration> for Registration {
+    fn from(registration: crate::api::registrations::Registration) -> Self {
+        Self {
+            contest_id: registration.contest_id as i32,
+            user_id: registration.user_id as i32,
+            registration_state: registration.state,
+            created_time: registration.created_time.naive_utc(),
+        }
+    }
+}
+
+/// Get the registration of a user for a contest, if any
+pub fn find_registration(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    uid: i32,
+) -> Result<Option<Registration>, Error> {
+    use self::registrations::dsl::*;
+
+    Ok(registrations.find((cid, uid)).first(conn).optional()?)
+}
+
+/// Get the registration of a user for a contest
+pub fn get_registration(
+    conn: &mut SqliteConnection,
+    cid: i32,
+    uid: i32,
+) -> Result<Registration, Error> {
+    find_registration(conn, cid, uid)?.ok_or_else(|| {
+        Error::new(
+            Reason::NotFound,
+            format!("User {uid} has not registered for contest {cid}"),
+        )
+    })
+}
+
+/// Get all registrations for a contest, in the order they were made
+pub fn get_registrations(
+    conn: &mut SqliteConnection,
+    cid: i32,
+) -> Result<Vec<Registration>, Error> {
+    use self::registrations::dsl::*;
+
+    Ok(registrations
+        .filter(contest_id.eq(cid))
+        .order((created_time.asc(), user_id.asc()))
+        .load(conn)?)
+}
+
+pub fn new_registration(
+    conn: &mut SqliteConnection,
+    registration: Registration,
+) -> Result<Registration, Error> {
+    use self::registrations::dsl::*;
+
+    diesel::insert_into(registrations)
+        .values(registration.clone())
+        .execute(conn)?;
+    Ok(registration)
+}
+
+pub fn update_registration(
+    conn: &mut SqliteConnection,
+    registration: Registration,
+) -> Result<Registration, Error> {
+    Ok(registration.save_changes(conn)?)
+}
+
+pub fn delete_registration(conn: &mut SqliteConnection, cid: i32, uid: i32) -> Result<(), Error> {
+    use self::registrations::dsl::*;
+
+    diesel::delete(registrations.find((cid, uid))).execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use diesel::prelude::*;
+
+use crate::api::err::Error;
+use crate::persistent::schema::teams;
+
+#[derive(Clone, Queryable, Insertable, Identifiable)]
+pub struct Team {
+    pub id: i32,
+    pub contest_id: i32,
+    pub team_name: String,
+    pub member_ids: String,
+}
+
+impl From<crate::api::teams::Team> for Team {
+    fn from(team: crate::api::teams::Team) -> Self {
+        Self {
+            id: team.id as i32,
+            contest_id: team.contest_id as i32,
+            team_name: team.name,
+            member_ids: team
+                .member_ids
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Get teams count
+pub fn teams_count(conn: &mut SqliteConnection) -> Result<i32, Error> {
+    use self::teams::dsl::*;
+
+    let count: i64 = teams.count().get_result(conn)?;
+
+    Ok(count as i32)
+}
+
+/// Get all teams in a contest, in the order they were registered
+pub fn get_teams(conn: &mut SqliteConnection, cid: i32) -> Result<Vec<Team>, Error> {
+    use self::teams::dsl::*;
+
+    Ok(teams
+        .filter(contest_id.eq(cid))
+        .order(id.asc())
+        .load(conn)?)
+}
+
+/// Replace the members of a team
+pub fn set_team_members(conn: &mut SqliteConnection, team: Team) -> Result<Team, Error> {
+    use self::teams::dsl::*;
+
+    diesel::update(teams.find(team.id))
+        .set(member_ids.eq(&team.member_ids))
+        .execute(conn)?;
+    Ok(team)
+}
+
+pub fn new_team(conn: &mut SqliteConnection, team: Team) -> Result<Team, Error> {
+    use self::teams::dsl::*;
+
+    diesel::insert_into(teams)
+        .values(team.clone())
+        .execute(conn)?;
+    Ok(team)
+}
@@ -1,5 +1,29 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    announcements (id) {
+        id -> Integer,
+        contest_id -> Integer,
+        problem_id -> Nullable<Integer>,
+        content -> Text,
+        created_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    clarifications (id) {
+        id -> Integer,
+        contest_id -> Integer,
+        problem_id -> Nullable<Integer>,
+        user_id -> Integer,
+        question -> Text,
+        answer -> Nullable<Text>,
+        public -> Bool,
+        created_time -> Timestamp,
+        updated_time -> Timestamp,
+    }
+}
+
 diesel::table! {
     contests (id) {
         id -> Integer,
@@ -9,6 +33,28 @@ diesel::table! {
         problem_ids -> Text,
         user_ids -> Text,
         submission_limit -> Integer,
+        stop_on_failure -> Bool,
+        hide_case_results -> Bool,
+        registration -> Nullable<Text>,
+        allow_upsolving -> Bool,
+        duration -> Nullable<Integer>,
+        problem_entries -> Text,
+        challenge_phase -> Nullable<Text>,
+        rated -> Bool,
+    }
+}
+
+diesel::table! {
+    hacks (id) {
+        id -> Integer,
+        contest_id -> Integer,
+        job_id -> Integer,
+        user_id -> Integer,
+        input -> Text,
+        hack_state -> Integer,
+        info -> Text,
+        created_time -> Timestamp,
+        updated_time -> Timestamp,
     }
 }
 
@@ -22,10 +68,51 @@ diesel::table! {
         user_id -> Integer,
         contest_id -> Integer,
         problem_id -> Integer,
+        submission_kind -> Integer,
         job_state -> Integer,
         result -> Integer,
         score -> Double,
         cases -> Text,
+        source_files -> Nullable<Text>,
+        answers -> Nullable<Text>,
+        team_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    participations (contest_id, user_id) {
+        contest_id -> Integer,
+        user_id -> Integer,
+        start_time -> Timestamp,
+        participation_kind -> Integer,
+    }
+}
+
+diesel::table! {
+    ratings (contest_id, user_id) {
+        contest_id -> Integer,
+        user_id -> Integer,
+        user_rank -> Integer,
+        old_rating -> Integer,
+        new_rating -> Integer,
+    }
+}
+
+diesel::table! {
+    registrations (contest_id, user_id) {
+        contest_id -> Integer,
+        user_id -> Integer,
+        registration_state -> Integer,
+        created_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    teams (id) {
+        id -> Integer,
+        contest_id -> Integer,
+        team_name -> Text,
+        member_ids -> Text,
     }
 }
 
@@ -40,5 +127,29 @@ diesel::table! {
 
 diesel::joinable!(jobs -> contests (contest_id));
 diesel::joinable!(jobs -> users (user_id));
+diesel::joinable!(announcements -> contests (contest_id));
+diesel::joinable!(clarifications -> contests (contest_id));
+diesel::joinable!(clarifications -> users (user_id));
+diesel::joinable!(hacks -> contests (contest_id));
+diesel::joinable!(hacks -> jobs (job_id));
+diesel::joinable!(hacks -> users (user_id));
+diesel::joinable!(participations -> contests (contest_id));
+diesel::joinable!(participations -> users (user_id));
+diesel::joinable!(ratings -> contests (contest_id));
+diesel::joinable!(ratings -> users (user_id));
+diesel::joinable!(registrations -> contests (contest_id));
+diesel::joinable!(registrations -> users (user_id));
+diesel::joinable!(teams -> contests (contest_id));
 
-diesel::allow_tables_to_appear_in_same_query!(contests, jobs, users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    announcements,
+    clarifications,
+    contests,
+    hacks,
+    jobs,
+    participations,
+    ratings,
+    registrations,
+    teams,
+    users,
+);
@@ -0,0 +1,86 @@
+use std::cmp::Reverse;
+
+/// Rating of a user who has never taken part in a rated contest
+pub const INITIAL_RATING: i32 = 1500;
+
+/// Bounds of the rating searched for when matching an expected rank
+const MIN_RATING: f64 = -4000.0;
+const MAX_RATING: f64 = 8000.0;
+
+/// Upper bound of the correction that keeps the ratings of the top contestants from inflating
+const MAX_TOP_CORRECTION: f64 = 10.0;
+
+/// A rated contestant, with their rating before the contest and their rank in it
+pub struct Contestant {
+    pub rating: i32,
+    /// Rank from 1, equal for contestants tied in the standings
+    pub rank: u32,
+}
+
+/// Probability that a contestant rated `a` ranks above one rated `b`
+fn win_probability(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+/// Expected rank of the contestant at `index` if they were rated `rating`
+fn seed(contestants: &[Contestant], index: usize, rating: f64) -> f64 {
+    1.0 + contestants
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != index)
+        .map(|(_, contestant)| win_probability(contestant.rating as f64, rating))
+        .sum::<f64>()
+}
+
+/// The rating at which the contestant at `index` would be expected to rank `rank`
+fn rating_for_rank(contestants: &[Contestant], index: usize, rank: f64) -> f64 {
+    let (mut low, mut high) = (MIN_RATING, MAX_RATING);
+    // The expected rank only gets better as the rating grows
+    while high - low > 1.0 {
+        let mid = (low + high) / 2.0;
+        if seed(contestants, index, mid) < rank {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    low
+}
+
+/// Compute the rating change of each contestant the way Codeforces does
+///
+/// Each contestant moves halfway towards the rating that would be expected of the geometric
+/// mean of their expected and actual ranks. The changes are then shifted so that they sum up
+/// to a little below zero, and the top rated contestants lose a little more to keep ratings
+/// from inflating.
+pub fn rating_changes(contestants: &[Contestant]) -> Vec<i32> {
+    let n = contestants.len();
+    if n == 0 {
+        return vec![];
+    }
+
+    let mut deltas: Vec<f64> = contestants
+        .iter()
+        .enumerate()
+        .map(|(index, contestant)| {
+            let rating = contestant.rating as f64;
+            let expected = seed(contestants, index, rating);
+            let rank = (expected * contestant.rank as f64).sqrt();
+            (rating_for_rank(contestants, index, rank) - rating) / 2.0
+        })
+        .collect();
+
+    let correction = -deltas.iter().sum::<f64>() / n as f64 - 1.0;
+    deltas.iter_mut().for_each(|delta| *delta += correction);
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&index| Reverse(contestants[index].rating));
+    let top = n.min(4 * (n as f64).sqrt().round() as usize);
+    let top_sum: f64 = order[..top].iter().map(|&index| deltas[index]).sum();
+    let correction = (-top_sum / top as f64).clamp(-MAX_TOP_CORRECTION, 0.0);
+
+    deltas
+        .iter()
+        .map(|delta| (delta + correction).round() as i32)
+        .collect()
+}
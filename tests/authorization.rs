@@ -0,0 +1,277 @@
+//! Tests of the behaviour that differs between roles, run with `--features authorization`
+#![cfg(feature = "authorization")]
+
+use reqwest::blocking::Client;
+use reqwest::header::{COOKIE, SET_COOKIE};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::env::consts::EXE_EXTENSION;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Once;
+use std::time::Duration;
+
+const PREFIX: &str = "http://127.0.0.1:12345";
+const ROOT_PASSWORD: &str = "#!/*<!--*#*SUPER_SECRET_PASSWORD*#*-->*/";
+
+/// Build the server with authorization, apart from the build used by the other tests
+fn build_and_find_path() -> PathBuf {
+    static CARGO_BUILD_ONCE: Once = Once::new();
+    let target_dir = Path::new("target").join("authorization");
+    CARGO_BUILD_ONCE.call_once(|| {
+        let mut build_command = Command::new("cargo");
+        build_command.args(["build", "--quiet", "--features", "authorization"]);
+        build_command.arg("--target-dir").arg(&target_dir);
+        if !cfg!(debug_assertions) {
+            build_command.arg("--release");
+        }
+        let build_status = build_command.status().unwrap();
+        assert!(
+            build_status.success(),
+            "Cargo failed to build associated binaries."
+        );
+    });
+    let flavor = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    target_dir
+        .join(flavor)
+        .join("oj")
+        .with_extension(EXE_EXTENSION)
+}
+
+/// A server running with a configuration, killed when dropped
+struct Server {
+    name: String,
+    client: Client,
+    process: Child,
+}
+
+impl Server {
+    fn start(name: &str) -> Self {
+        let client = Client::new();
+        // ensure no server is running
+        client.post(format!("{PREFIX}/internal/exit")).send().ok();
+        std::thread::sleep(Duration::from_secs(1));
+
+        let case_dir = Path::new("tests").join("cases");
+        let output = |extension: &str| {
+            File::create(case_dir.join(format!("{name}.{extension}")))
+                .map(Stdio::from)
+                .unwrap_or_else(|_| panic!("failed to create {extension} file"))
+        };
+        let process = Command::new(build_and_find_path())
+            .arg("--config")
+            .arg(case_dir.join(format!("{name}.config.json")))
+            .arg("--flush-data")
+            .stdout(output("stdout"))
+            .stderr(output("stderr"))
+            .spawn()
+            .unwrap_or_else(|_| panic!("case {name} incorrect: failed to execute server process"));
+        let server = Self {
+            name: name.to_string(),
+            client,
+            process,
+        };
+        for retry in 0..5 {
+            std::thread::sleep(Duration::from_secs(1));
+            match server.client.get(PREFIX).send() {
+                Ok(_) => break,
+                Err(err) if retry == 4 => {
+                    panic!("case {name} incorrect: failed to connect to OJ server ({err})")
+                }
+                Err(_) => continue,
+            }
+        }
+        server
+    }
+
+    /// Send a request as a logged in user, or anonymously, and get the status and the body
+    fn send(&self, cookie: Option<&str>, method: Method, path: &str, body: Value) -> (u16, Value) {
+        let mut request = self
+            .client
+            .request(method.clone(), format!("{PREFIX}/{path}"));
+        if let Some(cookie) = cookie {
+            request = request.header(COOKIE, cookie);
+        }
+        if method != Method::GET {
+            request = request.json(&body);
+        }
+        let response = request
+            .send()
+            .unwrap_or_else(|err| panic!("case {} incorrect: {err}", self.name));
+        let status = response.status().as_u16();
+        (status, response.json().unwrap_or(Value::Null))
+    }
+
+    /// Register a user with a role, and log in as them
+    fn user(&self, name: &str, role: &str) -> String {
+        let form = json!({ "username": name, "password": name });
+        let (status, _) = self.send(None, Method::POST, "register", form.clone());
+        assert_eq!(
+            status, 200,
+            "case {} incorrect: failed to register",
+            self.name
+        );
+        if role != "User" {
+            let root = self.login("root", ROOT_PASSWORD);
+            let form = json!({ "username": name, "role": role });
+            let (status, _) = self.send(Some(&root), Method::POST, "privilege", form);
+            assert_eq!(
+                status, 200,
+                "case {} incorrect: failed to promote",
+                self.name
+            );
+        }
+        self.login(name, name)
+    }
+
+    /// Log in and get the cookie to send along with later requests
+    fn login(&self, name: &str, password: &str) -> String {
+        let response = self
+            .client
+            .post(format!("{PREFIX}/login"))
+            .json(&json!({ "username": name, "password": password }))
+            .send()
+            .unwrap();
+        let cookie = response
+            .headers()
+            .get(SET_COOKIE)
+            .unwrap_or_else(|| panic!("case {} incorrect: failed to log in", self.name));
+        cookie
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Submit a job and wait for it to finish
+    fn judge(&self, cookie: &str, submission: Value) -> u64 {
+        let (status, job) = self.send(Some(cookie), Method::POST, "jobs", submission);
+        assert_eq!(
+            status, 200,
+            "case {} incorrect: failed to submit",
+            self.name
+        );
+        let id = job["id"].as_u64().unwrap();
+        for _ in 0..10 {
+            let (_, job) = self.send(Some(cookie), Method::GET, &format!("jobs/{id}"), json!({}));
+            if job["state"] == "Finished" {
+                return id;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        panic!("case {} incorrect: job {id} not finished", self.name);
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.process.kill().ok();
+    }
+}
+
+/// Info of the cases of a job, after the compilation result
+fn case_info(job: &Value) -> Vec<&str> {
+    job["cases"].as_array().unwrap()[1..]
+        .iter()
+        .map(|case| case["info"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_auth_01_case_redaction() {
+    // users see details of sample cases only, while authors see those of every case
+    let server = Server::start("auth_01_case_redaction");
+    let user = server.user("alice", "User");
+    let author = server.user("bob", "Author");
+    let id = server.judge(
+        &user,
+        json!({
+            "source_code": "use std::io::Read; fn main() { let mut s = String::new(); std::io::stdin().read_to_string(&mut s).unwrap(); let v: Vec<i64> = s.split_whitespace().map(|x| x.parse().unwrap()).collect(); println!(\"{}\", v[0] - v[1]); }",
+            "language": "Rust",
+            "user_id": 1,
+            "contest_id": 0,
+            "problem_id": 0
+        }),
+    );
+
+    let (_, job) = server.send(Some(&user), Method::GET, &format!("jobs/{id}"), json!({}));
+    let info = case_info(&job);
+    assert!(
+        info.len() == 2 && !info[0].is_empty() && info[1].is_empty(),
+        "case auth_01_case_redaction incorrect: only the sample case should be detailed to users"
+    );
+    let (_, jobs) = server.send(Some(&user), Method::GET, "jobs", json!({}));
+    assert_eq!(
+        case_info(&jobs[0]),
+        info,
+        "case auth_01_case_redaction incorrect: the job list should be redacted alike"
+    );
+
+    let (_, job) = server.send(Some(&author), Method::GET, &format!("jobs/{id}"), json!({}));
+    assert!(
+        case_info(&job).iter().all(|info| !info.is_empty()),
+        "case auth_01_case_redaction incorrect: every case should be detailed to authors"
+    );
+}
+
+#[test]
+fn test_auth_02_standings_export() {
+    // only authors can export the standings of a contest before it ends
+    let server = Server::start("auth_02_standings_export");
+    let user = server.user("alice", "User");
+    let author = server.user("bob", "Author");
+    let contest = json!({
+        "name": "Export Round",
+        "from": "2022-01-01T00:00:00.000Z",
+        "to": "2030-01-01T00:00:00.000Z",
+        "problem_ids": [0, 1],
+        "user_ids": [1],
+        "submission_limit": 10
+    });
+    let (status, _) = server.send(Some(&author), Method::POST, "contests", contest);
+    assert_eq!(
+        status, 200,
+        "case auth_02_standings_export incorrect: failed to create contest"
+    );
+
+    for path in ["contests/1/ranklist.csv", "contests/1/event-feed"] {
+        let (status, error) = server.send(Some(&user), Method::GET, path, json!({}));
+        assert_eq!(
+            (status, &error["reason"]),
+            (403, &json!("ERR_FORBIDDEN")),
+            "case auth_02_standings_export incorrect: users should not export {path}"
+        );
+        let (status, _) = server.send(Some(&author), Method::GET, path, json!({}));
+        assert_eq!(
+            status, 200,
+            "case auth_02_standings_export incorrect: authors should export {path}"
+        );
+    }
+
+    // Time limits are the most generous of the cases, where 0 is unlimited
+    let feed = server
+        .client
+        .get(format!("{PREFIX}/contests/1/event-feed"))
+        .header(COOKIE, &author)
+        .send()
+        .and_then(|response| response.text())
+        .unwrap();
+    let time_limits: Vec<Value> = feed
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|event| event["type"] == "problems")
+        .map(|event| event["data"]["time_limit"].clone())
+        .collect();
+    assert_eq!(
+        time_limits,
+        [json!(1.0), Value::Null],
+        "case auth_02_standings_export incorrect: unlimited time should be exported as null"
+    );
+}
@@ -0,0 +1,26 @@
+#include "verdict.h"
+
+int main(int argc, char *argv[]) {
+    if (argc < 4) {
+        quitf(_fail, "usage: checker input output answer");
+    }
+    FILE *output = std::fopen(argv[2], "r");
+    FILE *answer = std::fopen(argv[3], "r");
+    long long found, expected;
+    if (!answer || std::fscanf(answer, "%lld", &expected) != 1) {
+        quitf(_fail, "cannot read answer");
+    }
+    if (!output || std::fscanf(output, "%lld", &found) != 1) {
+        quitf(_pe, "expected an integer");
+    }
+    long long diff = std::llabs(found - expected);
+    if (diff == 0) {
+        quitf(_ok, "the sum is correct");
+    } else if (diff <= 10) {
+        quitp(0.5, "the sum is close");
+    } else if (diff <= 100) {
+        quitf(_pc(25), "the sum is not far");
+    } else {
+        quitf(_wa, "the sum is wrong");
+    }
+}
@@ -0,0 +1,25 @@
+// A tiny subset of testlib.h: report the verdict in the exit code and the message on stderr
+#include <cstdio>
+#include <cstdlib>
+
+enum Result { _ok = 0, _wa = 1, _pe = 2, _fail = 3, _points = 7, _partially = 16 };
+
+#define _pc(k) (Result)(_partially + (k))
+
+[[noreturn]] inline void quitf(Result result, const char *message) {
+    const char *name = "";
+    switch (result) {
+        case _ok: name = "ok "; break;
+        case _wa: name = "wrong answer "; break;
+        case _pe: name = "wrong output format "; break;
+        case _fail: name = "FAIL "; break;
+        default: name = "partially correct "; break;
+    }
+    std::fprintf(stderr, "%s%s\n", name, message);
+    std::exit(result);
+}
+
+[[noreturn]] inline void quitp(double points, const char *message) {
+    std::fprintf(stderr, "points %g %s\n", points, message);
+    std::exit(_points);
+}
@@ -0,0 +1,8 @@
+#include <cstdio>
+#include "plus.h"
+
+int main() {
+    int a, b;
+    scanf("%d%d", &a, &b);
+    printf("%d\n", plus(a, b));
+}
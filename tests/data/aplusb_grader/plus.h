@@ -0,0 +1 @@
+int plus(int a, int b);
@@ -0,0 +1,22 @@
+use std::io::Read;
+use std::process;
+
+// A valid input is two integers in [-10^9, 10^9] on a line
+fn main() {
+    let mut s = String::new();
+    std::io::stdin().read_to_string(&mut s).unwrap();
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    if s.lines().count() != 1 || tokens.len() != 2 {
+        eprintln!("Expected two integers on a line");
+        process::exit(1);
+    }
+    for token in tokens {
+        match token.parse::<i64>() {
+            Ok(x) if x.abs() <= 1_000_000_000 => (),
+            _ => {
+                eprintln!("Invalid integer: {token}");
+                process::exit(1);
+            }
+        }
+    }
+}
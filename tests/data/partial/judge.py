@@ -0,0 +1,22 @@
+#!/usr/bin/env python3
+import sys
+
+output = sys.argv[1]
+answer = sys.argv[2]
+
+output_number = float(open(output, 'r').read().strip())
+answer_number = float(open(answer, 'r').read().strip())
+
+error = abs((output_number - answer_number) / answer_number)
+if error < 0.1:
+    print('Accepted')
+    print('The error is within bounds')
+elif error < 0.3:
+    print('points 0.5')
+    print('The error is a bit large')
+elif error < 0.5:
+    print('0.25')
+    print('The error is large')
+else:
+    print('Wrong Answer')
+    print('The error is beyond bounds')
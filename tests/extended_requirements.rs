@@ -0,0 +1,380 @@
+mod common;
+use common::TestCase;
+
+#[test]
+fn test_ext_01_builtin_checkers() {
+    // check token-based and floating-point tolerant checkers
+    TestCase::read("ext_01_builtin_checkers").run();
+}
+
+#[test]
+fn test_ext_02_diff_details() {
+    // check line and column of the first difference, and the full diff
+    TestCase::read("ext_02_diff_details").run();
+}
+
+#[test]
+fn test_ext_03_output_limit() {
+    // print endlessly, then check that the job result is OLE
+    TestCase::read("ext_03_output_limit").run();
+}
+
+#[test]
+fn test_ext_04_file_io() {
+    // read from and write to named files in the working directory
+    TestCase::read("ext_04_file_io").run();
+}
+
+#[test]
+fn test_ext_05_grader() {
+    // link a function implementation against a trusted grader
+    TestCase::read("ext_05_grader").run();
+}
+
+#[test]
+fn test_ext_06_multi_file() {
+    // submit multiple files as a JSON map and as a gzipped tar archive
+    TestCase::read("ext_06_multi_file").run();
+}
+
+#[test]
+fn test_ext_07_answer_only() {
+    // submit the answer of each case directly instead of source code
+    TestCase::read("ext_07_answer_only").run();
+}
+
+#[test]
+fn test_ext_08_partial_scoring() {
+    // scale case scores by the fraction given by the special judge, and aggregate subtasks
+    TestCase::read("ext_08_partial_scoring").run();
+}
+
+#[test]
+fn test_ext_09_testlib_checker() {
+    // compile a checker following the testlib protocol, and map its exit codes to results
+    TestCase::read("ext_09_testlib_checker").run();
+}
+
+#[test]
+fn test_ext_10_compile_cache() {
+    // submit the same code twice, then check that the second one reuses the compile result
+    let results = TestCase::read("ext_10_compile_cache").run();
+    assert_eq!(results.len(), 4, "case ext_10_compile_cache incorrect");
+    let compile = |i: usize| &results[i]["cases"][0];
+    assert!(
+        compile(1)["time"].as_u64().unwrap() * 2 < compile(0)["time"].as_u64().unwrap(),
+        "case ext_10_compile_cache incorrect: compilation should be cached"
+    );
+    assert_eq!(
+        compile(2)["info"],
+        compile(3)["info"],
+        "case ext_10_compile_cache incorrect: compile errors should be cached"
+    );
+}
+
+#[test]
+fn test_ext_11_parallel_cases() {
+    // judge slow cases at the same time, then check that results are applied in order
+    TestCase::read("ext_11_parallel_cases").run();
+}
+
+#[test]
+fn test_ext_12_stop_on_failure() {
+    // stop judging at the first failure, as asked by either the problem or the contest
+    TestCase::read("ext_12_stop_on_failure").run();
+}
+
+#[test]
+fn test_ext_13_problem_statements() {
+    // get the metadata of all problems, then the statement and samples of each
+    TestCase::read("ext_13_problem_statements").run();
+}
+
+#[test]
+fn test_ext_14_contest_registration() {
+    // register for contests with invite codes and approval, then withdraw before it begins
+    let results = TestCase::read("ext_14_contest_registration").run();
+    assert_eq!(
+        results.len(),
+        26,
+        "case ext_14_contest_registration incorrect"
+    );
+    for i in [15, 22] {
+        assert_eq!(
+            results[i]["user_ids"].as_array().unwrap().len(),
+            0,
+            "case ext_14_contest_registration incorrect: contest 2 should have no users"
+        );
+    }
+    assert_eq!(
+        results[23].as_array().unwrap().len(),
+        1,
+        "case ext_14_contest_registration incorrect: withdrawn registration should be removed"
+    );
+    assert!(
+        results[25]["registration"]["code"].is_null(),
+        "case ext_14_contest_registration incorrect: the invite code should be hidden"
+    );
+}
+
+#[test]
+fn test_ext_15_virtual_participation() {
+    // participate in a past contest virtually, then rank with and without virtual participants
+    let results = TestCase::read("ext_15_virtual_participation").run();
+    assert_eq!(
+        results.len(),
+        14,
+        "case ext_15_virtual_participation incorrect"
+    );
+    let time = |value: &serde_json::Value| {
+        chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap()).unwrap()
+    };
+    assert_eq!(
+        (time(&results[7]["to"]) - time(&results[7]["from"])).num_seconds(),
+        60,
+        "case ext_15_virtual_participation incorrect: window should last as long as the contest"
+    );
+    assert_eq!(
+        results[11].as_array().unwrap().len(),
+        1,
+        "case ext_15_virtual_participation incorrect: virtual participants should be excluded"
+    );
+}
+
+#[test]
+fn test_ext_16_upsolving() {
+    // submit after the contest beyond the limit, then rank with and without upsolving
+    TestCase::read("ext_16_upsolving").run();
+}
+
+#[test]
+fn test_ext_17_personal_windows() {
+    // start a personal window in a contest, submit in it and rank with the time used
+    let results = TestCase::read("ext_17_personal_windows").run();
+    assert_eq!(results.len(), 11, "case ext_17_personal_windows incorrect");
+    let time = |value: &serde_json::Value| {
+        chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap()).unwrap()
+    };
+    assert_eq!(
+        (time(&results[5]["to"]) - time(&results[5]["from"])).num_seconds(),
+        10800,
+        "case ext_17_personal_windows incorrect: window should last as long as the duration"
+    );
+    assert!(
+        results[9][0]["time_used"].is_u64(),
+        "case ext_17_personal_windows incorrect: time used should be reported"
+    );
+    assert!(
+        results[10][0].get("time_used").is_none(),
+        "case ext_17_personal_windows incorrect: time used should be absent without windows"
+    );
+}
+
+#[test]
+fn test_ext_18_teams() {
+    // register a team before the contest, share the submission limit and rank as the team
+    let results = TestCase::read("ext_18_teams").run();
+    assert_eq!(results.len(), 14, "case ext_18_teams incorrect");
+    let ranklist = results[13].as_array().unwrap();
+    assert_eq!(
+        ranklist.len(),
+        2,
+        "case ext_18_teams incorrect: members should be ranked only as their team"
+    );
+    assert!(
+        ranklist[0].get("user").is_none(),
+        "case ext_18_teams incorrect: a team should be ranked instead of a user"
+    );
+}
+
+#[test]
+fn test_ext_19_clarifications() {
+    // ask and answer clarifications, publish announcements and poll for both
+    let results = TestCase::read("ext_19_clarifications").run();
+    assert_eq!(results.len(), 18, "case ext_19_clarifications incorrect");
+    assert_eq!(
+        results[11].as_array().unwrap().len(),
+        2,
+        "case ext_19_clarifications incorrect: all clarifications should be listed"
+    );
+    assert!(
+        results[12].as_array().unwrap().is_empty(),
+        "case ext_19_clarifications incorrect: nothing changed since then"
+    );
+    assert!(
+        results[16].as_array().unwrap().is_empty(),
+        "case ext_19_clarifications incorrect: announcements belong to their contest"
+    );
+}
+
+#[test]
+fn test_ext_20_contest_problems() {
+    // label problems in a contest, override their scores and limit submissions per problem
+    TestCase::read("ext_20_contest_problems").run();
+}
+
+#[test]
+fn test_ext_21_hacks() {
+    // hack accepted submissions in the challenge phase, and score problems the codeforces way
+    let results = TestCase::read("ext_21_hacks").run();
+    assert_eq!(results.len(), 22, "case ext_21_hacks incorrect");
+    // Solved almost right after starting, after a rejected attempt
+    let score = results[21][0]["scores"][0].as_f64().unwrap();
+    assert!(
+        score > 89.0 && score <= 90.0,
+        "case ext_21_hacks incorrect: the score should decay a little and lose a penalty"
+    );
+    assert!(
+        results[21][0].get("hack_score").is_none(),
+        "case ext_21_hacks incorrect: virtual participants can't hack"
+    );
+}
+
+#[test]
+fn test_ext_22_ratings() {
+    // finalize a rated contest once it ends, and look up the rating history of its participants
+    let results = TestCase::read("ext_22_ratings").run();
+    assert_eq!(results.len(), 16, "case ext_22_ratings incorrect");
+    let changes = results[11].as_array().unwrap();
+    assert_eq!(
+        changes.len(),
+        2,
+        "case ext_22_ratings incorrect: only those who submitted are rated"
+    );
+    assert!(
+        changes[0]["new_rating"].as_i64().unwrap() > 1500
+            && changes[1]["new_rating"].as_i64().unwrap() < 1500,
+        "case ext_22_ratings incorrect: the winner should gain and the loser should lose"
+    );
+    assert_eq!(
+        results[12]["rating"], changes[0]["new_rating"],
+        "case ext_22_ratings incorrect: the rating should follow the history"
+    );
+    assert!(
+        results[13]["history"].as_array().unwrap().is_empty(),
+        "case ext_22_ratings incorrect: carol has never been rated"
+    );
+    assert_eq!(
+        results[14], results[11],
+        "case ext_22_ratings incorrect: finalizing again should change nothing"
+    );
+}
+
+#[test]
+fn test_ext_23_standings_export() {
+    // export the rank list of a contest as csv, and its submissions as an icpc event feed
+    TestCase::read("ext_23_standings_export").run();
+}
+
+#[test]
+fn test_ext_24_wall_time() {
+    // sleeping takes no cpu time, but is stopped by the wall clock limit
+    let results = TestCase::read("ext_24_wall_time").run();
+    let slept = &results[0]["cases"][1];
+    assert!(
+        slept["time"].as_u64().unwrap() < 500_000
+            && slept["wall_time"].as_u64().unwrap() >= 1_000_000,
+        "case ext_24_wall_time incorrect: only cpu time counts against the time limit"
+    );
+    let killed = results[1]["cases"][1]["wall_time"].as_u64().unwrap();
+    assert!(
+        (1_500_000..3_000_000).contains(&killed),
+        "case ext_24_wall_time incorrect: the program should be killed at the wall clock limit"
+    );
+}
+
+#[test]
+fn test_ext_25_nested_executable_cache() {
+    // build into a directory created by make, then check that the build is cached all the same
+    let results = TestCase::read("ext_25_nested_executable_cache").run();
+    let compile = |i: usize| &results[i]["cases"][0];
+    assert!(
+        compile(1)["time"].as_u64().unwrap() * 2 < compile(0)["time"].as_u64().unwrap(),
+        "case ext_25_nested_executable_cache incorrect: compilation should be cached"
+    );
+}
+
+#[test]
+fn test_ext_26_case_redaction() {
+    // hide case results during a contest that asks to, while details of other jobs stay visible
+    let results = TestCase::read("ext_26_case_redaction").run();
+    assert_eq!(results.len(), 4, "case ext_26_case_redaction incorrect");
+    // Without authorization, nobody is unprivileged
+    let cases = results[0]["cases"].as_array().unwrap();
+    assert!(
+        cases.len() == 3 && cases[1..].iter().all(|case| case["info"] != ""),
+        "case ext_26_case_redaction incorrect: both sample and hidden cases should be detailed"
+    );
+    assert_eq!(
+        results[2]["cases"].as_array().unwrap().len(),
+        1,
+        "case ext_26_case_redaction incorrect: only the compilation result should be shown"
+    );
+    assert_eq!(
+        results[3][0]["cases"].as_array().unwrap().len(),
+        1,
+        "case ext_26_case_redaction incorrect: case results should be hidden in the job list"
+    );
+}
+
+#[test]
+fn test_ext_27_hack_rejudge() {
+    // judge the input of a successful hack as an extra case, so that rejudging keeps the verdict,
+    // and reward the hack in proportion to the scores of the problems
+    let results = TestCase::read("ext_27_hack_rejudge").run();
+    assert_eq!(results.len(), 11, "case ext_27_hack_rejudge incorrect");
+    let mut states: Vec<_> = results[7]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hack| hack["state"].as_str().unwrap())
+        .collect();
+    states.sort();
+    assert_eq!(
+        states,
+        ["Invalid Input", "Successful"],
+        "case ext_27_hack_rejudge incorrect: only one of the same hacks at once should succeed"
+    );
+    for i in [8, 9] {
+        assert_eq!(
+            results[i]["cases"].as_array().unwrap().len(),
+            3,
+            "case ext_27_hack_rejudge incorrect: the hack should be the only extra case"
+        );
+    }
+}
+
+#[test]
+fn test_ext_28_hidden_problems() {
+    // hide the problems of a contest from everyone until it begins
+    let results = TestCase::read("ext_28_hidden_problems").run();
+    assert_eq!(results.len(), 7, "case ext_28_hidden_problems incorrect");
+    assert_eq!(
+        results[1].as_array().unwrap().len(),
+        1,
+        "case ext_28_hidden_problems incorrect: the problem of the upcoming contest should be hidden"
+    );
+}
+
+#[test]
+fn test_ext_29_team_membership() {
+    // keep teams out of contests with personal windows, and drop members leaving the contest
+    let results = TestCase::read("ext_29_team_membership").run();
+    assert_eq!(results.len(), 16, "case ext_29_team_membership incorrect");
+    assert_eq!(
+        results[11][0]["member_ids"].as_array().unwrap().len(),
+        1,
+        "case ext_29_team_membership incorrect: the withdrawn user should leave the team"
+    );
+    assert!(
+        results[13].as_array().unwrap().is_empty(),
+        "case ext_29_team_membership incorrect: a team without members should be disbanded"
+    );
+}
+
+#[test]
+fn test_ext_30_hack_limits() {
+    // run hacks under the most generous limits, where 0 is unlimited, and only on problems with cases
+    let results = TestCase::read("ext_30_hack_limits").run();
+    assert_eq!(results.len(), 9, "case ext_30_hack_limits incorrect");
+}